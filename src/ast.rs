@@ -1,3 +1,4 @@
+use crate::location::LocationSpan;
 use crate::names::*;
 use crate::parser::token::Token;
 
@@ -10,23 +11,57 @@ pub struct Program {
 pub enum TopLevelItem {
     Def(Definition),
     Expr(AstExpression),
+    /// `require "foo"` (resolved by `runner` before creating hir)
+    Require(String),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Definition {
     ClassDefinition {
         name: ClassFirstname,
-        typarams: Vec<String>,
+        typarams: Vec<AstTyParam>,
         super_name: ClassFullname,
+        /// Interfaces listed after the superclass (eg. `class A : B, C, D<E>`)
+        interfaces: Vec<Typ>,
+        defs: Vec<Definition>,
+        span: Option<LocationSpan>,
+    },
+    EnumDefinition {
+        name: ClassFirstname,
+        typarams: Vec<AstTyParam>,
+        cases: Vec<EnumCase>,
         defs: Vec<Definition>,
+        span: Option<LocationSpan>,
+    },
+    ModuleDefinition {
+        name: ClassFirstname,
+        defs: Vec<Definition>,
+        span: Option<LocationSpan>,
+    },
+    /// `interface Foo ... end`
+    InterfaceDefinition {
+        name: ClassFirstname,
+        typarams: Vec<AstTyParam>,
+        sigs: Vec<AstMethodSignature>,
+        span: Option<LocationSpan>,
+    },
+    /// `include Foo` in a class body
+    IncludeDefinition {
+        name: ConstName,
+        span: Option<LocationSpan>,
     },
     InstanceMethodDefinition {
         sig: AstMethodSignature,
         body_exprs: Vec<AstExpression>,
+        span: Option<LocationSpan>,
+        /// The module which defines this method, if it is copied into a
+        /// class by `include` (set by `hir::mixin`)
+        module: Option<ClassFullname>,
     },
     ClassMethodDefinition {
         sig: AstMethodSignature,
         body_exprs: Vec<AstExpression>,
+        span: Option<LocationSpan>,
     },
     ConstDefinition {
         name: ConstFirstname,
@@ -34,19 +69,41 @@ pub enum Definition {
     },
 }
 
-#[derive(Debug, PartialEq)]
+/// A case of an enum
+/// eg. `case Value(v: T)`
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumCase {
+    pub name: ClassFirstname,
+    pub params: Vec<Param>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct AstMethodSignature {
     pub name: MethodFirstname,
-    pub typarams: Vec<String>,
+    pub typarams: Vec<AstTyParam>,
     pub params: Vec<Param>,
     pub ret_typ: Typ,
 }
 
+/// A type parameter of a class or a method
+/// eg. `T`, `T : Comparable`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstTyParam {
+    pub name: String,
+    pub upper_bound: Option<Typ>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Param {
     pub name: String,
-    pub typ: Typ,
-    pub is_iparam: bool, // eg. `def initialize(@a: Int)`
+    /// None if omitted (only allowed for block params, eg. `|x|`)
+    pub typ: Option<Typ>,
+    pub is_iparam: bool,                     // eg. `def initialize(@a: Int)`
+    pub is_rest: bool,                       // eg. `def foo(*a: Int)`
+    pub default_expr: Option<AstExpression>, // eg. `def foo(a: Int = 1)`
+    /// Names of the elements when destructuring a tuple (eg. `|(k, v)|`).
+    /// `name` is a hidden name of the whole tuple in this case
+    pub destructure: Option<Vec<String>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -55,10 +112,19 @@ pub struct Typ {
     pub typ_args: Vec<Typ>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct AstExpression {
     pub body: AstExpressionBody,
     pub primary: bool,
+    /// None if the expression is not from the source (eg. made by the compiler)
+    pub span: Option<LocationSpan>,
+}
+
+/// Spans are not compared so that a parsed AST can be compared with the one made by hand
+impl PartialEq for AstExpression {
+    fn eq(&self, other: &AstExpression) -> bool {
+        self.body == other.body && self.primary == other.primary
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -74,6 +140,13 @@ pub enum AstExpressionBody {
         left: Box<AstExpression>,
         right: Box<AstExpression>,
     },
+    /// `a..b` or `a...b`
+    Range {
+        first: Box<AstExpression>,
+        last: Box<AstExpression>,
+        /// true if `...`
+        exclude_end: bool,
+    },
     If {
         cond_expr: Box<AstExpression>,
         then_exprs: Vec<AstExpression>,
@@ -83,10 +156,37 @@ pub enum AstExpressionBody {
         cond_expr: Box<AstExpression>,
         body_exprs: Vec<AstExpression>,
     },
+    /// `for x in expr`
+    For {
+        var_name: String,
+        iterable: Box<AstExpression>,
+        body_exprs: Vec<AstExpression>,
+    },
+    Match {
+        cond_expr: Box<AstExpression>,
+        clauses: Vec<AstMatchClause>,
+        else_exprs: Option<Vec<AstExpression>>,
+    },
+    Case {
+        cond_expr: Box<AstExpression>,
+        clauses: Vec<AstCaseClause>,
+        else_exprs: Option<Vec<AstExpression>>,
+    },
+    Begin {
+        body_exprs: Vec<AstExpression>,
+        rescue_clauses: Vec<AstRescueClause>,
+        ensure_exprs: Option<Vec<AstExpression>>,
+    },
     Break,
+    Next {
+        arg: Option<Box<AstExpression>>,
+    },
     Return {
         arg: Option<Box<AstExpression>>,
     },
+    Raise {
+        expr: Box<AstExpression>,
+    },
     LVarAssign {
         name: String,
         rhs: Box<AstExpression>,
@@ -103,6 +203,12 @@ pub enum AstExpressionBody {
         names: Vec<String>,
         rhs: Box<AstExpression>,
     },
+    /// `a, b = pair` or `a, b = b, a`
+    MultipleAssignment {
+        names: Vec<String>,
+        /// Has only one element when destructuring a tuple
+        rhs: Vec<AstExpression>,
+    },
     MethodCall {
         receiver_expr: Option<Box<AstExpression>>, // Box is needed to aboid E0072
         method_name: MethodFirstname,
@@ -110,6 +216,15 @@ pub enum AstExpressionBody {
         type_args: Vec<ConstName>,
         may_have_paren_wo_args: bool,
     },
+    /// `name: value` in the args of a method call
+    KeywordArg {
+        name: String,
+        value: Box<AstExpression>,
+    },
+    /// `*expr` in the args of a method call
+    SplatArg {
+        expr: Box<AstExpression>,
+    },
     LambdaExpr {
         params: Vec<Param>,
         exprs: Vec<AstExpression>,
@@ -122,6 +237,10 @@ pub enum AstExpressionBody {
     ConstRef(ConstName),
     PseudoVariable(Token),
     ArrayLiteral(Vec<AstExpression>),
+    /// `(a, b, ...)`
+    TupleLiteral(Vec<AstExpression>),
+    /// `{k1 => v1, k2 => v2, ...}`
+    HashLiteral(Vec<(AstExpression, AstExpression)>),
     FloatLiteral {
         value: f64,
     },
@@ -133,6 +252,37 @@ pub enum AstExpressionBody {
     },
 }
 
+/// A clause of `match`
+/// eg. `when Node(l, r) then ...`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstMatchClause {
+    /// Class to match (eg. `Node`)
+    pub class_name: ConstName,
+    /// Names of the local variables to bind the ivars to (eg. `l`, `r`)
+    pub bindings: Vec<String>,
+    pub body_exprs: Vec<AstExpression>,
+}
+
+/// A clause of `case`
+/// eg. `when 1, 2 then ...`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstCaseClause {
+    /// Values to compare with `==`
+    pub values: Vec<AstExpression>,
+    pub body_exprs: Vec<AstExpression>,
+}
+
+/// A clause of `begin`
+/// eg. `rescue ArgumentError, RuntimeError => e`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstRescueClause {
+    /// Classes to rescue. Empty if not specified (i.e. `StandardError`)
+    pub class_names: Vec<ConstName>,
+    /// Name of the local variable to bind the exception to
+    pub var_name: Option<String>,
+    pub body_exprs: Vec<AstExpression>,
+}
+
 impl Definition {
     pub fn is_initializer(&self) -> bool {
         match self {
@@ -140,6 +290,60 @@ impl Definition {
             _ => false,
         }
     }
+
+    /// Returns where this definition is in the source
+    pub fn span(&self) -> Option<LocationSpan> {
+        match self {
+            Definition::ClassDefinition { span, .. }
+            | Definition::EnumDefinition { span, .. }
+            | Definition::ModuleDefinition { span, .. }
+            | Definition::InterfaceDefinition { span, .. }
+            | Definition::IncludeDefinition { span, .. }
+            | Definition::InstanceMethodDefinition { span, .. }
+            | Definition::ClassMethodDefinition { span, .. } => span.clone(),
+            Definition::ConstDefinition { expr, .. } => expr.span.clone(),
+        }
+    }
+}
+
+impl EnumCase {
+    /// Returns `#initialize` which sets the fields of this case.
+    /// None if this case has no fields
+    pub fn initializer(&self) -> Option<Definition> {
+        if self.params.is_empty() {
+            return None;
+        }
+        let params = self
+            .params
+            .iter()
+            .map(|param| Param {
+                name: format!("@{}", param.name),
+                typ: param.typ.clone(),
+                is_iparam: true,
+                is_rest: param.is_rest,
+                default_expr: param.default_expr.clone(),
+                destructure: None,
+            })
+            .collect::<Vec<_>>();
+        let body_exprs = params
+            .iter()
+            .map(|param| ivar_assign(param.name.clone(), bare_name(&param.name)))
+            .collect();
+        Some(Definition::InstanceMethodDefinition {
+            sig: AstMethodSignature {
+                name: method_firstname("initialize"),
+                typarams: vec![],
+                params,
+                ret_typ: Typ {
+                    name: "Void".to_string(),
+                    typ_args: vec![],
+                },
+            },
+            body_exprs,
+            span: None,
+            module: None,
+        })
+    }
 }
 
 impl AstExpression {
@@ -166,6 +370,28 @@ impl AstExpression {
             _ => false,
         }
     }
+
+    pub fn is_keyword_arg(&self) -> bool {
+        match self.body {
+            AstExpressionBody::KeywordArg { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_splat_arg(&self) -> bool {
+        match self.body {
+            AstExpressionBody::SplatArg { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Return true if this is a block (`{|x| ...}` or `do |x| ... end`)
+    pub fn is_block(&self) -> bool {
+        match self.body {
+            AstExpressionBody::LambdaExpr { is_fn, .. } => !is_fn,
+            _ => false,
+        }
+    }
 }
 
 pub fn logical_not(expr: AstExpression) -> AstExpression {
@@ -188,6 +414,14 @@ pub fn logical_or(left: AstExpression, right: AstExpression) -> AstExpression {
     })
 }
 
+pub fn range_expr(first: AstExpression, last: AstExpression, exclude_end: bool) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Range {
+        first: Box::new(first),
+        last: Box::new(last),
+        exclude_end,
+    })
+}
+
 pub fn if_expr(
     cond_expr: AstExpression,
     then_exprs: Vec<AstExpression>,
@@ -207,16 +441,76 @@ pub fn while_expr(cond_expr: AstExpression, body_exprs: Vec<AstExpression>) -> A
     })
 }
 
+pub fn for_expr(
+    var_name: String,
+    iterable: AstExpression,
+    body_exprs: Vec<AstExpression>,
+) -> AstExpression {
+    non_primary_expression(AstExpressionBody::For {
+        var_name,
+        iterable: Box::new(iterable),
+        body_exprs,
+    })
+}
+
+pub fn match_expr(
+    cond_expr: AstExpression,
+    clauses: Vec<AstMatchClause>,
+    else_exprs: Option<Vec<AstExpression>>,
+) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Match {
+        cond_expr: Box::new(cond_expr),
+        clauses,
+        else_exprs,
+    })
+}
+
+pub fn case_expr(
+    cond_expr: AstExpression,
+    clauses: Vec<AstCaseClause>,
+    else_exprs: Option<Vec<AstExpression>>,
+) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Case {
+        cond_expr: Box::new(cond_expr),
+        clauses,
+        else_exprs,
+    })
+}
+
+pub fn begin_expr(
+    body_exprs: Vec<AstExpression>,
+    rescue_clauses: Vec<AstRescueClause>,
+    ensure_exprs: Option<Vec<AstExpression>>,
+) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Begin {
+        body_exprs,
+        rescue_clauses,
+        ensure_exprs,
+    })
+}
+
 pub fn break_expr() -> AstExpression {
     non_primary_expression(AstExpressionBody::Break {})
 }
 
+pub fn next_expr(arg: Option<AstExpression>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Next {
+        arg: arg.map(Box::new),
+    })
+}
+
 pub fn return_expr(arg: Option<AstExpression>) -> AstExpression {
     non_primary_expression(AstExpressionBody::Return {
         arg: arg.map(|x| Box::new(x)),
     })
 }
 
+pub fn raise_expr(expr: AstExpression) -> AstExpression {
+    non_primary_expression(AstExpressionBody::Raise {
+        expr: Box::new(expr),
+    })
+}
+
 /// Create an expression for an assigment
 pub fn assignment(lhs: AstExpression, rhs: AstExpression) -> AstExpression {
     let body = match lhs.body {
@@ -254,6 +548,10 @@ pub fn assignment(lhs: AstExpression, rhs: AstExpression) -> AstExpression {
     non_primary_expression(body)
 }
 
+pub fn multiple_assignment(names: Vec<String>, rhs: Vec<AstExpression>) -> AstExpression {
+    non_primary_expression(AstExpressionBody::MultipleAssignment { names, rhs })
+}
+
 pub fn lvar_decl(name: String, rhs: AstExpression) -> AstExpression {
     non_primary_expression(AstExpressionBody::LVarAssign {
         name,
@@ -295,9 +593,23 @@ pub fn method_call(
             type_args,
             may_have_paren_wo_args,
         },
+        span: None,
     }
 }
 
+pub fn keyword_arg(name: String, value: AstExpression) -> AstExpression {
+    non_primary_expression(AstExpressionBody::KeywordArg {
+        name,
+        value: Box::new(value),
+    })
+}
+
+pub fn splat_arg(expr: AstExpression) -> AstExpression {
+    non_primary_expression(AstExpressionBody::SplatArg {
+        expr: Box::new(expr),
+    })
+}
+
 pub fn bare_name(name: &str) -> AstExpression {
     primary_expression(AstExpressionBody::BareName(name.to_string()))
 }
@@ -346,6 +658,14 @@ pub fn array_literal(exprs: Vec<AstExpression>) -> AstExpression {
     primary_expression(AstExpressionBody::ArrayLiteral(exprs))
 }
 
+pub fn tuple_literal(exprs: Vec<AstExpression>) -> AstExpression {
+    primary_expression(AstExpressionBody::TupleLiteral(exprs))
+}
+
+pub fn hash_literal(pairs: Vec<(AstExpression, AstExpression)>) -> AstExpression {
+    primary_expression(AstExpressionBody::HashLiteral(pairs))
+}
+
 pub fn float_literal(value: f64) -> AstExpression {
     primary_expression(AstExpressionBody::FloatLiteral { value })
 }
@@ -362,6 +682,7 @@ pub fn primary_expression(body: AstExpressionBody) -> AstExpression {
     AstExpression {
         primary: true,
         body,
+        span: None,
     }
 }
 
@@ -369,6 +690,7 @@ pub fn non_primary_expression(body: AstExpressionBody) -> AstExpression {
     AstExpression {
         primary: false,
         body,
+        span: None,
     }
 }
 
@@ -399,6 +721,7 @@ pub fn set_method_call_args(expr: AstExpression, args: Vec<AstExpression>) -> As
                     type_args,
                     may_have_paren_wo_args: false,
                 },
+                span: None,
             }
         }
         AstExpressionBody::BareName(s) => AstExpression {
@@ -410,6 +733,7 @@ pub fn set_method_call_args(expr: AstExpression, args: Vec<AstExpression>) -> As
                 type_args: vec![],
                 may_have_paren_wo_args: false,
             },
+            span: None,
         },
         b => panic!("[BUG] `extend' takes a MethodCall but got {:?}", b),
     }
@@ -13,12 +13,43 @@ pub struct CodeGenContext<'hir: 'run, 'run> {
     pub function_params: Option<&'hir [MethodParam]>,
     /// Ptr of local variables
     pub lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
+    /// Beginning (the condition) of `while`, if any
+    pub current_loop_begin: Option<Rc<inkwell::basic_block::BasicBlock<'run>>>,
     /// End of `while`, if any
     pub current_loop_end: Option<Rc<inkwell::basic_block::BasicBlock<'run>>>,
     /// End of the current llvm function. Only used for lambdas
     pub current_func_end: Rc<inkwell::basic_block::BasicBlock<'run>>,
     /// Arguments of `return` found in this context
     pub returns: Vec<(inkwell::values::BasicValueEnum<'run>, inkwell::basic_block::BasicBlock<'run>)>,
+    /// `begin`s which the current code is in (innermost last)
+    pub rescue_frames: Vec<RescueFrame<'run>>,
+    /// Length of `rescue_frames` when entering the current `while`
+    pub loop_rescue_frames: usize,
+}
+
+/// A handler installed by `begin`
+#[derive(Debug)]
+pub struct RescueFrame<'run> {
+    /// The handler to restore when leaving the `begin`
+    pub prev_handler: inkwell::values::PointerValue<'run>,
+    /// Number of the running methods when entering the `begin`
+    pub depth: inkwell::values::IntValue<'run>,
+    /// True if returned from setjmp by longjmp
+    pub raised: inkwell::values::IntValue<'run>,
+    /// `ensure` clause to run when leaving the `begin`, if any
+    pub ensure: Option<EnsureFrame<'run>>,
+}
+
+/// The `ensure` clause of a `begin`. Its code is generated once and every
+/// path which leaves the `begin` jumps to it
+#[derive(Debug)]
+pub struct EnsureFrame<'run> {
+    /// The block which runs the `ensure` clause
+    pub block: inkwell::basic_block::BasicBlock<'run>,
+    /// Index of `dests` to jump to after running the clause
+    pub dest_idx: inkwell::values::PointerValue<'run>,
+    /// Blocks to jump to after running the clause
+    pub dests: Vec<inkwell::basic_block::BasicBlock<'run>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -48,9 +79,12 @@ impl<'hir, 'run> CodeGenContext<'hir, 'run> {
             function_origin,
             function_params,
             lvars,
+            current_loop_begin: None,
             current_loop_end: None,
             current_func_end: function_end,
             returns: Default::default(),
+            rescue_frames: vec![],
+            loop_rescue_frames: 0,
         }
     }
 }
@@ -0,0 +1,556 @@
+/// Exceptions are implemented with setjmp/longjmp.
+///
+/// - `@shiika_rescue_buf` points the jmp_buf of the innermost `begin`
+///   (null if there is none)
+/// - `raise` stores the exception to `@shiika_exception` and longjmp's to it
+/// - `@shiika_frames` records the names of the running methods so that
+///   uncaught exceptions can print a backtrace
+use crate::code_gen::code_gen_context::*;
+use crate::code_gen::*;
+use crate::error::Error;
+use crate::ty;
+use inkwell::attributes::{Attribute, AttributeLoc};
+
+/// Number of method names kept in `@shiika_frames` (must be a power of 2)
+const MAX_FRAMES: u64 = 1024;
+
+impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
+    /// Declare the functions and globals for exceptions
+    pub(super) fn gen_exception_declares(&self) {
+        let fn_type = self.i32_type.fn_type(&[self.i8ptr_type.into()], false);
+        let func = self.module.add_function("_setjmp", fn_type, None);
+        let kind = Attribute::get_named_enum_kind_id("returns_twice");
+        func.add_attribute(
+            AttributeLoc::Function,
+            self.context.create_enum_attribute(kind, 0),
+        );
+        let fn_type = self
+            .void_type
+            .fn_type(&[self.i8ptr_type.into(), self.i32_type.into()], false);
+        self.module.add_function("longjmp", fn_type, None);
+
+        for name in &["shiika_rescue_buf", "shiika_exception"] {
+            let global = self.module.add_global(self.i8ptr_type, None, name);
+            global.set_linkage(inkwell::module::Linkage::Internal);
+            global.set_initializer(&self.i8ptr_type.const_null());
+        }
+        let ary_type = self.i8ptr_type.array_type(MAX_FRAMES as u32);
+        let global = self.module.add_global(ary_type, None, "shiika_frames");
+        global.set_linkage(inkwell::module::Linkage::Internal);
+        global.set_initializer(&ary_type.const_zero());
+        let global = self
+            .module
+            .add_global(self.i64_type, None, "shiika_frame_depth");
+        global.set_linkage(inkwell::module::Linkage::Internal);
+        global.set_initializer(&self.i64_type.const_int(0, false));
+    }
+
+    /// Generate `@shiika_raise` and `@shiika_uncaught`
+    pub(super) fn gen_exception_funcs(&self) {
+        self.gen_uncaught_func();
+        self.gen_raise_func();
+    }
+
+    /// Generate `void @shiika_raise(i8* %exc)`
+    fn gen_raise_func(&self) {
+        let fn_type = self.void_type.fn_type(&[self.i8ptr_type.into()], false);
+        let function = self.module.add_function("shiika_raise", fn_type, None);
+        let entry_block = self.context.append_basic_block(function, "");
+        let jump_block = self.context.append_basic_block(function, "Jump");
+        let uncaught_block = self.context.append_basic_block(function, "Uncaught");
+        self.builder.position_at_end(entry_block);
+        let exc = function.get_first_param().unwrap();
+        self.builder
+            .build_store(self.global_ptr("shiika_exception"), exc);
+        let buf = self
+            .builder
+            .build_load(self.global_ptr("shiika_rescue_buf"), "buf")
+            .into_pointer_value();
+        let is_null = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            buf,
+            self.i8ptr_type.const_null(),
+            "is_null",
+        );
+        self.builder
+            .build_conditional_branch(is_null, uncaught_block, jump_block);
+        // Jump:
+        self.builder.position_at_end(jump_block);
+        let one = self.i32_type.const_int(1, false);
+        self.builder
+            .build_call(self.get_llvm_func("longjmp"), &[buf.into(), one.into()], "");
+        self.builder.build_unreachable();
+        // Uncaught:
+        self.builder.position_at_end(uncaught_block);
+        self.builder
+            .build_call(self.get_llvm_func("shiika_uncaught"), &[exc], "");
+        self.builder.build_unreachable();
+    }
+
+    /// Generate `void @shiika_uncaught(i8* %exc)` which prints the
+    /// exception with the backtrace and exits
+    fn gen_uncaught_func(&self) {
+        let fn_type = self.void_type.fn_type(&[self.i8ptr_type.into()], false);
+        let function = self.module.add_function("shiika_uncaught", fn_type, None);
+        let entry_block = self.context.append_basic_block(function, "");
+        let loop_block = self.context.append_basic_block(function, "Loop");
+        let body_block = self.context.append_basic_block(function, "Body");
+        let done_block = self.context.append_basic_block(function, "Done");
+        self.builder.position_at_end(entry_block);
+        let exc_ty = ty::raw("Exception");
+        let exc = self.builder.build_bitcast(
+            function.get_first_param().unwrap(),
+            self.llvm_type(&exc_ty),
+            "exc",
+        );
+
+        let cls_obj = self.build_class_object_load(exc);
+        let name = self.build_ivar_load(cls_obj, 0, "@name");
+        let name_ptr = self.unbox_i8ptr(self.build_ivar_load(name, 0, "@ptr"));
+
+        // Call `#message` (may be overridden)
+        let (idx, size) = self
+            .vtables
+            .method_idx(&exc_ty, &method_firstname("message"));
+        let func_raw = self.build_vtable_ref(exc, *idx, size);
+        let func_type = self
+            .llvm_func_type(Some(&exc_ty), &[], &ty::raw("String"))
+            .ptr_type(AddressSpace::Generic);
+        let func = self
+            .builder
+            .build_bitcast(func_raw, func_type, "func")
+            .into_pointer_value();
+        let msg = self
+            .builder
+            .build_call(func, &[exc], "msg")
+            .try_as_basic_value()
+            .left()
+            .unwrap();
+        let msg_ptr = self.unbox_i8ptr(self.build_ivar_load(msg, 0, "@ptr"));
+        let printf = self.get_llvm_func("printf");
+        let fmt = self.c_str("Uncaught %s: %s\n");
+        self.builder
+            .build_call(printf, &[fmt.into(), name_ptr.into(), msg_ptr.into()], "");
+
+        // Print the frames from the innermost (only the last MAX_FRAMES of them)
+        let depth = self
+            .builder
+            .build_load(self.global_ptr("shiika_frame_depth"), "depth")
+            .into_int_value();
+        let max = self.i64_type.const_int(MAX_FRAMES, false);
+        let overflowed =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::UGT, depth, max, "overflowed");
+        let lowest = self.builder.build_int_sub(depth, max, "lowest");
+        let lowest = self
+            .builder
+            .build_select(
+                overflowed,
+                lowest,
+                self.i64_type.const_int(0, false),
+                "lowest",
+            )
+            .into_int_value();
+        self.builder.build_unconditional_branch(loop_block);
+        // Loop:
+        self.builder.position_at_end(loop_block);
+        let i = self.builder.build_phi(self.i64_type, "i");
+        let i_value = i.as_basic_value().into_int_value();
+        let more =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::UGT, i_value, lowest, "more");
+        self.builder
+            .build_conditional_branch(more, body_block, done_block);
+        // Body:
+        self.builder.position_at_end(body_block);
+        let i_next =
+            self.builder
+                .build_int_sub(i_value, self.i64_type.const_int(1, false), "i_next");
+        let frame_name = self
+            .builder
+            .build_load(self.frame_slot(i_next), "frame_name");
+        let fmt = self.c_str("  from %s\n");
+        self.builder
+            .build_call(printf, &[fmt.into(), frame_name], "");
+        self.builder.build_unconditional_branch(loop_block);
+        i.add_incoming(&[(&depth, entry_block), (&i_next, body_block)]);
+        // Done:
+        self.builder.position_at_end(done_block);
+        let fmt = self.c_str("  from <main>\n");
+        self.builder.build_call(printf, &[fmt.into()], "");
+        let status = self.i32_type.const_int(1, false);
+        self.builder
+            .build_call(self.get_llvm_func("exit"), &[status.into()], "");
+        self.builder.build_unreachable();
+    }
+
+    /// Record that the method `name` is called
+    pub(super) fn gen_push_frame(&self, name: &str) {
+        let depth_ptr = self.global_ptr("shiika_frame_depth");
+        let depth = self.builder.build_load(depth_ptr, "depth").into_int_value();
+        self.builder
+            .build_store(self.frame_slot(depth), self.c_str(name));
+        let one = self.i64_type.const_int(1, false);
+        let new_depth = self.builder.build_int_add(depth, one, "depth");
+        self.builder.build_store(depth_ptr, new_depth);
+    }
+
+    /// Record that the current method returns
+    pub(super) fn gen_pop_frame(&self) {
+        let depth_ptr = self.global_ptr("shiika_frame_depth");
+        let depth = self.builder.build_load(depth_ptr, "depth").into_int_value();
+        let one = self.i64_type.const_int(1, false);
+        let new_depth = self.builder.build_int_sub(depth, one, "depth");
+        self.builder.build_store(depth_ptr, new_depth);
+    }
+
+    /// Generate `raise`
+    pub(super) fn gen_raise_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        expr: &'hir HirExpression,
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        let value = self.gen_expr(ctx, expr)?;
+        self.build_raise(value);
+        // Following instructions (if any) are never executed
+        let block = self.context.append_basic_block(ctx.function, "AfterRaise");
+        self.builder.position_at_end(block);
+        Ok(self.i1_type.const_int(0, false).as_basic_value_enum())
+    }
+
+    /// Generate reference to the exception being rescued
+    pub(super) fn gen_current_exception(
+        &self,
+        ty: &TermTy,
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        let exc = self
+            .builder
+            .build_load(self.global_ptr("shiika_exception"), "exc");
+        Ok(self.builder.build_bitcast(exc, self.llvm_type(ty), "exc"))
+    }
+
+    /// Generate `begin ... rescue ... ensure ... end`
+    pub(super) fn gen_begin_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &TermTy,
+        body_exprs: &'hir HirExpressions,
+        rescue_clauses: &'hir [HirRescueClause],
+        ensure_exprs: &'hir Option<Box<HirExpressions>>,
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        let ensure_exprs = match ensure_exprs {
+            Some(exprs) => exprs,
+            None => return self.gen_rescue(ctx, ty, body_exprs, rescue_clauses),
+        };
+        let mut frame = self.build_setjmp(ctx);
+        let body_block = self.context.append_basic_block(ctx.function, "EnsureBody");
+        let raised_block = self
+            .context
+            .append_basic_block(ctx.function, "EnsureRaised");
+        let ensure_block = self.context.append_basic_block(ctx.function, "Ensure");
+        let end_block = self.context.append_basic_block(ctx.function, "EnsureEnd");
+        let reraise_block = self
+            .context
+            .append_basic_block(ctx.function, "EnsureReraise");
+        let dest_idx = self.build_entry_alloca(ctx.function, self.i32_type, "ensure_dest");
+        let exc_ptr = self.build_entry_alloca(ctx.function, self.i8ptr_type, "ensure_exc");
+        self.builder
+            .build_conditional_branch(frame.raised, raised_block, body_block);
+        // EnsureRaised:
+        self.builder.position_at_end(raised_block);
+        self.build_restore_handler(&frame);
+        // Keep the exception as the `ensure` clause may raise and rescue another one
+        let exc = self
+            .builder
+            .build_load(self.global_ptr("shiika_exception"), "exc");
+        self.builder.build_store(exc_ptr, exc);
+        self.build_ensure_jump(dest_idx, 1, ensure_block);
+        // EnsureBody:
+        self.builder.position_at_end(body_block);
+        frame.ensure = Some(EnsureFrame {
+            block: ensure_block,
+            dest_idx,
+            dests: vec![end_block, reraise_block],
+        });
+        ctx.rescue_frames.push(frame);
+        let value = self.gen_rescue(ctx, ty, body_exprs, rescue_clauses)?;
+        let frame = ctx.rescue_frames.pop().unwrap();
+        self.build_restore_handler(&frame);
+        // EnsureEnd is not dominated by this block
+        let value_ptr = self.build_entry_alloca(ctx.function, value.get_type(), "begin_value");
+        self.builder.build_store(value_ptr, value);
+        self.build_ensure_jump(dest_idx, 0, ensure_block);
+        // Ensure:
+        self.builder.position_at_end(ensure_block);
+        self.gen_exprs(ctx, ensure_exprs)?;
+        let dest = self.builder.build_load(dest_idx, "dest").into_int_value();
+        let dests = frame.ensure.unwrap().dests;
+        let cases = dests
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, block)| (self.i32_type.const_int(i as u64, false), *block))
+            .collect::<Vec<_>>();
+        self.builder.build_switch(dest, end_block, &cases);
+        // EnsureReraise:
+        self.builder.position_at_end(reraise_block);
+        let exc = self.builder.build_load(exc_ptr, "exc");
+        self.build_raise(exc);
+        // EnsureEnd:
+        self.builder.position_at_end(end_block);
+        Ok(self.builder.build_load(value_ptr, "value"))
+    }
+
+    /// Generate `begin ... rescue ... end`
+    fn gen_rescue(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &TermTy,
+        body_exprs: &'hir HirExpressions,
+        rescue_clauses: &'hir [HirRescueClause],
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        if rescue_clauses.is_empty() {
+            return self.gen_exprs(ctx, body_exprs);
+        }
+        let frame = self.build_setjmp(ctx);
+        let body_block = self.context.append_basic_block(ctx.function, "BeginBody");
+        let rescue_block = self.context.append_basic_block(ctx.function, "Rescue");
+        let end_block = self.context.append_basic_block(ctx.function, "BeginEnd");
+        self.builder
+            .build_conditional_branch(frame.raised, rescue_block, body_block);
+
+        let mut incomings = vec![];
+        let mut never_blocks = vec![];
+        // BeginBody:
+        self.builder.position_at_end(body_block);
+        ctx.rescue_frames.push(frame);
+        let body_value = self.gen_exprs(ctx, body_exprs)?;
+        let frame = ctx.rescue_frames.pop().unwrap();
+        self.build_restore_handler(&frame);
+        self.builder.build_unconditional_branch(end_block);
+        let body_block_end = self.builder.get_insert_block().unwrap();
+        if body_exprs.ty.is_never_type() {
+            never_blocks.push(body_block_end);
+        } else {
+            incomings.push((body_value, body_block_end));
+        }
+
+        // Rescue:
+        self.builder.position_at_end(rescue_block);
+        self.build_restore_handler(&frame);
+        let exc = self
+            .builder
+            .build_load(self.global_ptr("shiika_exception"), "exc");
+        let obj = self
+            .builder
+            .build_bitcast(exc, self.llvm_type(&ty::raw("Object")), "obj");
+        for clause in rescue_clauses {
+            let clause_block = self.context.append_basic_block(ctx.function, "RescueBody");
+            let next_block = self.context.append_basic_block(ctx.function, "RescueNext");
+            let matched = self.build_is_instance_of(obj, &clause.classes);
+            self.builder
+                .build_conditional_branch(matched, clause_block, next_block);
+            // RescueBody:
+            self.builder.position_at_end(clause_block);
+            let clause_value = self.gen_exprs(ctx, &clause.body_exprs)?;
+            self.builder.build_unconditional_branch(end_block);
+            let clause_block_end = self.builder.get_insert_block().unwrap();
+            if clause.body_exprs.ty.is_never_type() {
+                never_blocks.push(clause_block_end);
+            } else {
+                incomings.push((clause_value, clause_block_end));
+            }
+            // RescueNext:
+            self.builder.position_at_end(next_block);
+        }
+        // No clause matched
+        self.build_raise(exc);
+
+        // BeginEnd:
+        self.builder.position_at_end(end_block);
+        if incomings.is_empty() {
+            // All the clauses are `Never`
+            return Ok(self.i1_type.const_int(0, false).as_basic_value_enum());
+        }
+        let llvm_ty = self.llvm_type(ty);
+        let phi_node = self.builder.build_phi(llvm_ty, "beginResult");
+        for (value, block) in &incomings {
+            phi_node.add_incoming(&[(value, *block)]);
+        }
+        // Never-typed clauses do not reach here but phi needs a value for each predecessor
+        let null = llvm_ty.into_pointer_type().const_null();
+        for block in &never_blocks {
+            phi_node.add_incoming(&[(&null, *block)]);
+        }
+        Ok(phi_node.as_basic_value())
+    }
+
+    /// Restore the handlers (and run the `ensure` clauses) of the `begin`s
+    /// which are left by `return`, `break` or `next`.
+    /// Returns `value` (the argument of `return`, etc.) which can be used
+    /// after the `ensure` clauses
+    pub(super) fn gen_leave_rescue_frames(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        n_keep: usize,
+        value: Option<inkwell::values::BasicValueEnum<'run>>,
+    ) -> Option<inkwell::values::BasicValueEnum<'run>> {
+        let has_ensure = ctx.rescue_frames[n_keep..]
+            .iter()
+            .any(|frame| frame.ensure.is_some());
+        // The blocks after the `ensure` clauses are not dominated by this block
+        let value_ptr = match value {
+            Some(v) if has_ensure => {
+                let ptr = self.build_entry_alloca(ctx.function, v.get_type(), "leave_value");
+                self.builder.build_store(ptr, v);
+                Some(ptr)
+            }
+            _ => None,
+        };
+        let function = ctx.function;
+        // The code after `return` or `break` is still in the `begin`s, so the
+        // frames are not popped
+        for frame in ctx.rescue_frames[n_keep..].iter_mut().rev() {
+            self.build_restore_handler(frame);
+            if let Some(ensure) = &mut frame.ensure {
+                let after_block = self.context.append_basic_block(function, "AfterEnsure");
+                self.build_ensure_jump(ensure.dest_idx, ensure.dests.len(), ensure.block);
+                ensure.dests.push(after_block);
+                self.builder.position_at_end(after_block);
+            }
+        }
+        match value_ptr {
+            Some(ptr) => Some(self.builder.build_load(ptr, "value")),
+            None => value,
+        }
+    }
+
+    /// Jump to the `ensure` clause, which then jumps to the `idx`th
+    /// destination
+    fn build_ensure_jump(
+        &self,
+        dest_idx: inkwell::values::PointerValue<'run>,
+        idx: usize,
+        ensure_block: inkwell::basic_block::BasicBlock<'run>,
+    ) {
+        self.builder
+            .build_store(dest_idx, self.i32_type.const_int(idx as u64, false));
+        self.builder.build_unconditional_branch(ensure_block);
+    }
+
+    /// Install a new handler and call setjmp
+    fn build_setjmp(&self, ctx: &CodeGenContext<'hir, 'run>) -> RescueFrame<'run> {
+        let buf = self.build_entry_alloca(
+            ctx.function,
+            self.i64_type.array_type(jmp_buf_words()),
+            "jmp_buf",
+        );
+        let buf = self
+            .builder
+            .build_bitcast(buf, self.i8ptr_type, "buf")
+            .into_pointer_value();
+        let handler_ptr = self.global_ptr("shiika_rescue_buf");
+        let prev_handler = self
+            .builder
+            .build_load(handler_ptr, "prev_handler")
+            .into_pointer_value();
+        let depth = self
+            .builder
+            .build_load(self.global_ptr("shiika_frame_depth"), "depth")
+            .into_int_value();
+        self.builder.build_store(handler_ptr, buf);
+        let result = self
+            .builder
+            .build_call(self.get_llvm_func("_setjmp"), &[buf.into()], "setjmp")
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let raised = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            result,
+            self.i32_type.const_int(0, false),
+            "raised",
+        );
+        RescueFrame {
+            prev_handler,
+            depth,
+            raised,
+            ensure: None,
+        }
+    }
+
+    /// Uninstall the handler of `frame`
+    fn build_restore_handler(&self, frame: &RescueFrame<'run>) {
+        self.builder
+            .build_store(self.global_ptr("shiika_rescue_buf"), frame.prev_handler);
+        self.builder
+            .build_store(self.global_ptr("shiika_frame_depth"), frame.depth);
+    }
+
+    /// Call `@shiika_raise`
+    fn build_raise(&self, exc: inkwell::values::BasicValueEnum<'run>) {
+        let exc = self.builder.build_bitcast(exc, self.i8ptr_type, "exc");
+        self.builder
+            .build_call(self.get_llvm_func("shiika_raise"), &[exc], "");
+        self.builder.build_unreachable();
+    }
+
+    /// Allocate a variable at the top of the function so that the stack
+    /// does not grow when the `begin` is in a loop
+    fn build_entry_alloca<T: BasicType<'run>>(
+        &self,
+        function: inkwell::values::FunctionValue<'run>,
+        ty: T,
+        name: &str,
+    ) -> inkwell::values::PointerValue<'run> {
+        let builder = self.context.create_builder();
+        let entry = function.get_first_basic_block().unwrap();
+        match entry.get_first_instruction() {
+            Some(inst) => builder.position_before(&inst),
+            None => builder.position_at_end(entry),
+        }
+        builder.build_alloca(ty, name)
+    }
+
+    /// Pointer to the `idx`th element of `@shiika_frames`
+    fn frame_slot(
+        &self,
+        idx: inkwell::values::IntValue<'run>,
+    ) -> inkwell::values::PointerValue<'run> {
+        let mask = self.i64_type.const_int(MAX_FRAMES - 1, false);
+        let idx = self.builder.build_and(idx, mask, "idx");
+        let zero = self.i64_type.const_int(0, false);
+        unsafe {
+            self.builder
+                .build_in_bounds_gep(self.global_ptr("shiika_frames"), &[zero, idx], "slot")
+        }
+    }
+
+    /// Pointer to a llvm global
+    fn global_ptr(&self, name: &str) -> inkwell::values::PointerValue<'run> {
+        self.module
+            .get_global(name)
+            .unwrap_or_else(|| panic!("[BUG] global `{}' not found", name))
+            .as_pointer_value()
+    }
+
+    /// Create a C string constant and return a pointer to it
+    fn c_str(&self, s: &str) -> inkwell::values::PointerValue<'run> {
+        self.builder
+            .build_global_string_ptr(s, "cstr")
+            .as_pointer_value()
+    }
+}
+
+/// Size of jmp_buf in words. The module has no target triple, so llc
+/// compiles it for the host
+fn jmp_buf_words() -> u32 {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => 25,
+        ("linux", "aarch64") => 39,
+        ("macos", "x86_64") => 19,
+        ("macos", "aarch64") => 24,
+        (os, arch) => panic!("size of jmp_buf is unknown for {}-{}", arch, os),
+    }
+}
@@ -53,9 +53,20 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             HirWhileExpression {
                 cond_expr,
                 body_exprs,
-            } => self.gen_while_expr(ctx, &cond_expr, &body_exprs),
+            } => self.gen_while_expr(ctx, &expr.ty, &cond_expr, &body_exprs),
+            HirMatchExpression {
+                cond_assign_expr,
+                clauses,
+            } => self.gen_match_expr(ctx, &expr.ty, &cond_assign_expr, &clauses),
+            HirBeginExpression {
+                body_exprs,
+                rescue_clauses,
+                ensure_exprs,
+            } => self.gen_begin_expr(ctx, &expr.ty, &body_exprs, &rescue_clauses, &ensure_exprs),
             HirBreakExpression { from } => self.gen_break_expr(ctx, from),
+            HirNextExpression { from, arg } => self.gen_next_expr(ctx, from, arg),
             HirReturnExpression { from, arg } => self.gen_return_expr(ctx, arg, from),
+            HirRaiseExpression { expr } => self.gen_raise_expr(ctx, &expr),
             HirLVarAssign { name, rhs } => self.gen_lvar_assign(ctx, name, rhs),
             HirIVarAssign {
                 name,
@@ -75,6 +86,14 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                 arg_exprs,
             } => self.gen_lambda_invocation(ctx, lambda_expr, arg_exprs, &expr.ty),
             HirArgRef { idx } => self.gen_arg_ref(ctx, idx),
+            HirOmittedArg => Ok(self
+                .llvm_type(&expr.ty)
+                .into_pointer_type()
+                .const_null()
+                .into()),
+            HirArgOrDefault { idx, default_expr } => {
+                self.gen_arg_or_default(ctx, &expr.ty, idx, default_expr)
+            }
             HirLVarRef { name } => self.gen_lvar_ref(ctx, name),
             HirIVarRef { name, idx, self_ty } => self.gen_ivar_ref(ctx, name, idx, self_ty),
             HirConstRef { fullname } => Ok(self.gen_const_ref(fullname)),
@@ -98,11 +117,14 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             HirLambdaCaptureWrite { cidx, rhs } => {
                 self.gen_lambda_capture_write(ctx, cidx, rhs, &rhs.ty)
             }
+            HirCurrentException => self.gen_current_exception(&expr.ty),
+            HirSequence { exprs } => self.gen_exprs(ctx, exprs),
             HirBitCast { expr: target } => self.gen_bitcast(ctx, target, &expr.ty),
             HirClassLiteral {
                 fullname,
                 str_literal_idx,
             } => Ok(self.gen_class_literal(fullname, str_literal_idx)),
+            HirErrorExpression => Err(error::bug("HirErrorExpression reached code_gen")),
         }
     }
 
@@ -229,6 +251,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     fn gen_while_expr(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &TermTy,
         cond_expr: &'hir HirExpression,
         body_exprs: &'hir HirExpressions,
     ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
@@ -244,16 +267,92 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.builder.position_at_end(body_block);
         let rc1 = Rc::new(end_block);
         let rc2 = Rc::clone(&rc1);
-        ctx.current_loop_end = Some(rc1);
+        let outer_loop_begin = ctx.current_loop_begin.replace(Rc::new(begin_block));
+        let outer_loop_end = ctx.current_loop_end.replace(rc1);
+        let outer_rescue_frames = ctx.loop_rescue_frames;
+        ctx.loop_rescue_frames = ctx.rescue_frames.len();
         self.gen_exprs(ctx, body_exprs)?;
-        ctx.current_loop_end = None;
+        ctx.loop_rescue_frames = outer_rescue_frames;
+        ctx.current_loop_begin = outer_loop_begin;
+        ctx.current_loop_end = outer_loop_end;
         self.builder.build_unconditional_branch(begin_block);
 
         // WhileEnd:
         self.builder.position_at_end(*rc2);
+        if ty.is_never_type() {
+            // `while true` without `break` never reaches here
+            self.builder.build_unreachable();
+            let block = self.context.append_basic_block(ctx.function, "AfterLoop");
+            self.builder.position_at_end(block);
+        }
         Ok(self.gen_const_ref(&const_fullname("::Void")))
     }
 
+    fn gen_match_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &TermTy,
+        cond_assign_expr: &'hir HirExpression,
+        clauses: &'hir [HirMatchClause],
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        let begin_block = self.context.append_basic_block(ctx.function, "MatchBegin");
+        let end_block = self.context.append_basic_block(ctx.function, "MatchEnd");
+        // MatchBegin:
+        self.builder.build_unconditional_branch(begin_block);
+        self.builder.position_at_end(begin_block);
+        let value = self.gen_expr(ctx, cond_assign_expr)?;
+
+        let mut incomings = vec![];
+        let mut never_blocks = vec![];
+        for clause in clauses {
+            let body_block = self.context.append_basic_block(ctx.function, "MatchBody");
+            // `else` clause has no classes (and is always the last one)
+            let next_block = if clause.classes.is_empty() {
+                self.builder.build_unconditional_branch(body_block);
+                None
+            } else {
+                let next_block = self.context.append_basic_block(ctx.function, "MatchNext");
+                let matched = self.build_is_instance_of(value, &clause.classes);
+                self.builder
+                    .build_conditional_branch(matched, body_block, next_block);
+                Some(next_block)
+            };
+            // MatchBody:
+            self.builder.position_at_end(body_block);
+            let clause_value = self.gen_exprs(ctx, &clause.body_exprs)?;
+            self.builder.build_unconditional_branch(end_block);
+            let body_block_end = self.builder.get_insert_block().unwrap();
+            if clause.body_exprs.ty.is_never_type() {
+                never_blocks.push(body_block_end);
+            } else {
+                incomings.push((clause_value, body_block_end));
+            }
+            // MatchNext:
+            match next_block {
+                Some(b) => self.builder.position_at_end(b),
+                None => break,
+            }
+        }
+
+        // MatchEnd:
+        self.builder.position_at_end(end_block);
+        if incomings.is_empty() {
+            // All the clauses are `Never`
+            return Ok(self.i1_type.const_int(0, false).as_basic_value_enum());
+        }
+        let llvm_ty = self.llvm_type(ty);
+        let phi_node = self.builder.build_phi(llvm_ty, "matchResult");
+        for (clause_value, block) in &incomings {
+            phi_node.add_incoming(&[(clause_value, *block)]);
+        }
+        // Never-typed clauses do not reach here but phi needs a value for each predecessor
+        let null = llvm_ty.into_pointer_type().const_null();
+        for block in &never_blocks {
+            phi_node.add_incoming(&[(&null, *block)]);
+        }
+        Ok(phi_node.as_basic_value())
+    }
+
     fn gen_break_expr(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
@@ -261,15 +360,19 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
         let dummy_value = self.i1_type.const_int(0, false).as_basic_value_enum();
         match from {
-            HirBreakFrom::While => match &ctx.current_loop_end {
-                Some(b) => {
-                    self.builder.build_unconditional_branch(*Rc::clone(b));
-                    Ok(dummy_value)
+            HirBreakFrom::While => {
+                self.gen_leave_rescue_frames(ctx, ctx.loop_rescue_frames, None);
+                match &ctx.current_loop_end {
+                    Some(b) => {
+                        self.builder.build_unconditional_branch(*Rc::clone(b));
+                        Ok(dummy_value)
+                    }
+                    None => Err(error::bug("break outside of a loop")),
                 }
-                None => Err(error::bug("break outside of a loop")),
-            },
+            }
             HirBreakFrom::Block => {
                 debug_assert!(ctx.function_origin == FunctionOrigin::Lambda);
+                self.gen_leave_rescue_frames(ctx, 0, None);
                 // Set @exit_status
                 let fn_x = ctx.function.get_first_param().unwrap();
                 let i = self.box_int(&self.i64_type.const_int(EXIT_BREAK, false));
@@ -283,6 +386,38 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         }
     }
 
+    fn gen_next_expr(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        from: &HirNextFrom,
+        arg: &'hir HirExpression,
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        let dummy_value = self.i1_type.const_int(0, false).as_basic_value_enum();
+        match from {
+            HirNextFrom::While => {
+                self.gen_leave_rescue_frames(ctx, ctx.loop_rescue_frames, None);
+                match &ctx.current_loop_begin {
+                    Some(b) => {
+                        self.builder.build_unconditional_branch(*Rc::clone(b));
+                        Ok(dummy_value)
+                    }
+                    None => Err(error::bug("next outside of a loop")),
+                }
+            }
+            HirNextFrom::Block => {
+                debug_assert!(ctx.function_origin == FunctionOrigin::Lambda);
+                // Return from the lambda with `@exit_status` untouched
+                let value = self.gen_expr(ctx, arg)?;
+                let value = self.gen_leave_rescue_frames(ctx, 0, Some(value)).unwrap();
+                self.builder
+                    .build_unconditional_branch(*Rc::clone(&ctx.current_func_end));
+                let block_end = self.builder.get_insert_block().unwrap();
+                ctx.returns.push((value, block_end));
+                Ok(dummy_value)
+            }
+        }
+    }
+
     fn gen_return_expr(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
@@ -291,6 +426,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
         let value = self.gen_expr(ctx, arg)?;
         let dummy_value = self.i1_type.const_int(0, false).as_basic_value_enum();
+        let value = self.gen_leave_rescue_frames(ctx, 0, Some(value)).unwrap();
         // Jump to the end of the llvm func
         self.builder
             .build_unconditional_branch(*Rc::clone(&ctx.current_func_end));
@@ -371,9 +507,17 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .context
             .append_basic_block(ctx.function, &format!("Invoke_{}_end", method_fullname));
 
-        // Get the llvm function from vtable
-        let (idx, size) = self.vtables.method_idx(&receiver_expr.ty, &method_name);
-        let func_raw = self.build_vtable_ref(receiver_value, *idx, size);
+        // Get the llvm function from itable (if the method belongs to an
+        // interface) or vtable
+        let func_raw = match self.itables.method_idx(method_fullname) {
+            Some((iface_idx, idx, size)) => {
+                self.build_itable_ref(receiver_value, iface_idx, idx, size)
+            }
+            None => {
+                let (idx, size) = self.vtables.method_idx(&receiver_expr.ty, &method_name);
+                self.build_vtable_ref(receiver_value, *idx, size)
+            }
+        };
         let func_type = self
             .llvm_func_type(
                 Some(&receiver_expr.ty),
@@ -526,6 +670,45 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         }
     }
 
+    /// Generate IR for HirArgOrDefault.
+    /// An omitted arg is passed as a null pointer (see HirOmittedArg)
+    fn gen_arg_or_default(
+        &self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        ty: &TermTy,
+        idx: &usize,
+        default_expr: &'hir HirExpression,
+    ) -> Result<inkwell::values::BasicValueEnum<'run>, Error> {
+        let arg = self.gen_arg_ref(ctx, idx)?;
+        let default_block = self.context.append_basic_block(ctx.function, "ArgDefault");
+        let end_block = self
+            .context
+            .append_basic_block(ctx.function, "ArgDefaultEnd");
+        let given_block = self.builder.get_insert_block().unwrap();
+        let llvm_ty = self.llvm_type(ty).into_pointer_type();
+        let is_omitted = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            arg.into_pointer_value(),
+            llvm_ty.const_null(),
+            "is_omitted",
+        );
+        self.builder
+            .build_conditional_branch(is_omitted, default_block, end_block);
+        // ArgDefault:
+        self.builder.position_at_end(default_block);
+        let default_value = self.gen_expr(ctx, default_expr)?;
+        self.builder.build_unconditional_branch(end_block);
+        let default_block_end = self.builder.get_insert_block().unwrap();
+        // ArgDefaultEnd:
+        self.builder.position_at_end(end_block);
+        if default_expr.ty.is_never_type() {
+            return Ok(arg);
+        }
+        let phi_node = self.builder.build_phi(llvm_ty, "argOrDefault");
+        phi_node.add_incoming(&[(&arg, given_block), (&default_value, default_block_end)]);
+        Ok(phi_node.as_basic_value())
+    }
+
     fn gen_lvar_ref(
         &self,
         ctx: &mut CodeGenContext<'hir, 'run>,
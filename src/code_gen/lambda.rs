@@ -60,8 +60,32 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
                 self.gen_lambda_funcs_in_expr(cond_expr)?;
                 self.gen_lambda_funcs_in_exprs(&body_exprs.exprs)?;
             }
+            HirMatchExpression {
+                cond_assign_expr,
+                clauses,
+            } => {
+                self.gen_lambda_funcs_in_expr(cond_assign_expr)?;
+                for clause in clauses {
+                    self.gen_lambda_funcs_in_exprs(&clause.body_exprs.exprs)?;
+                }
+            }
+            HirBeginExpression {
+                body_exprs,
+                rescue_clauses,
+                ensure_exprs,
+            } => {
+                self.gen_lambda_funcs_in_exprs(&body_exprs.exprs)?;
+                for clause in rescue_clauses {
+                    self.gen_lambda_funcs_in_exprs(&clause.body_exprs.exprs)?;
+                }
+                if let Some(exprs) = ensure_exprs {
+                    self.gen_lambda_funcs_in_exprs(&exprs.exprs)?;
+                }
+            }
             HirBreakExpression { .. } => (),
+            HirNextExpression { arg, .. } => self.gen_lambda_funcs_in_expr(arg)?,
             HirReturnExpression { arg, .. } => self.gen_lambda_funcs_in_expr(arg)?,
+            HirRaiseExpression { expr } => self.gen_lambda_funcs_in_expr(expr)?,
             HirLVarAssign { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
             HirIVarAssign { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
             HirConstAssign { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
@@ -82,6 +106,8 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
                 }
             }
             HirArgRef { .. } => (),
+            HirOmittedArg => (),
+            HirArgOrDefault { default_expr, .. } => self.gen_lambda_funcs_in_expr(default_expr)?,
             HirLVarRef { .. } => (),
             HirIVarRef { .. } => (),
             HirConstRef { .. } => (),
@@ -105,8 +131,11 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
 
             HirLambdaCaptureRef { .. } => (),
             HirLambdaCaptureWrite { rhs, .. } => self.gen_lambda_funcs_in_expr(rhs)?,
+            HirCurrentException => (),
+            HirSequence { exprs } => self.gen_lambda_funcs_in_exprs(&exprs.exprs)?,
             HirBitCast { expr } => self.gen_lambda_funcs_in_expr(expr)?,
             HirClassLiteral { .. } => (),
+            HirErrorExpression => (),
         }
         Ok(())
     }
@@ -1,10 +1,13 @@
 mod boxing;
 mod code_gen_context;
+mod exception;
 mod gen_exprs;
 mod lambda;
 mod utils;
 use crate::code_gen::code_gen_context::*;
-use crate::code_gen::utils::llvm_vtable_name;
+use crate::code_gen::utils::{
+    llvm_itable_name, llvm_itables_name, llvm_vtable_name, VTABLE_HEADER_SIZE,
+};
 use crate::error;
 use crate::error::Error;
 use crate::hir::*;
@@ -39,7 +42,9 @@ pub struct CodeGen<'hir: 'ictx, 'run, 'ictx: 'run> {
     pub void_type: inkwell::types::VoidType<'ictx>,
     pub llvm_struct_types: HashMap<ClassFullname, inkwell::types::StructType<'ictx>>,
     str_literals: &'hir Vec<String>,
+    sk_classes: &'hir HashMap<ClassFullname, SkClass>,
     vtables: &'hir mir::VTables,
+    itables: &'hir mir::ITables,
     /// Toplevel `self`
     the_main: Option<inkwell::values::BasicValueEnum<'ictx>>,
 }
@@ -78,20 +83,25 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             void_type: context.void_type(),
             llvm_struct_types: HashMap::new(),
             str_literals: &mir.hir.str_literals,
+            sk_classes: &mir.hir.sk_classes,
             vtables: &mir.vtables,
+            itables: &mir.itables,
             the_main: None,
         }
     }
 
     pub fn gen_program(&mut self, hir: &'hir Hir) -> Result<(), Error> {
         self.gen_declares();
+        self.gen_exception_declares();
         self.gen_class_structs(&hir.sk_classes);
         self.gen_string_literals(&hir.str_literals);
         self.gen_constant_ptrs(&hir.constants);
         self.gen_boxing_funcs();
         self.gen_method_funcs(&hir.sk_methods);
+        self.gen_itables();
         self.gen_vtables();
         self.impl_boxing_funcs();
+        self.gen_exception_funcs();
         self.gen_methods(&hir.sk_methods)?;
         self.gen_const_inits(&hir.const_inits)?;
         self.gen_user_main(&hir.main_exprs, &hir.main_lvars)?;
@@ -163,31 +173,97 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
     }
 
     // Generate vtable constants
+    // The 0th element is the reference to the itables of the class (null if
+    // the class does not conform to any interface)
     fn gen_vtables(&self) {
         for (class_fullname, vtable) in self.vtables.iter() {
             let method_names = vtable.to_vec();
-            let ary_type = self.i8ptr_type.array_type(method_names.len() as u32);
+            let ary_type = self
+                .i8ptr_type
+                .array_type((VTABLE_HEADER_SIZE + method_names.len()) as u32);
             let global = self
                 .module
                 .add_global(ary_type, None, &llvm_vtable_name(class_fullname));
             global.set_constant(true);
             global.set_linkage(inkwell::module::Linkage::Internal);
-            let func_ptrs = method_names
+            let itables_ref = match self.module.get_global(&llvm_itables_name(class_fullname)) {
+                Some(itables) => self
+                    .builder
+                    .build_bitcast(itables.as_pointer_value(), self.i8ptr_type, "")
+                    .into_pointer_value(),
+                None => self.i8ptr_type.const_null(),
+            };
+            let mut func_ptrs = vec![itables_ref];
+            func_ptrs.extend(method_names.iter().map(|name| {
+                let func = self
+                    .get_llvm_func(&name.full_name)
+                    .as_any_value_enum()
+                    .into_pointer_value();
+                self.builder
+                    .build_bitcast(func, self.i8ptr_type, "")
+                    .into_pointer_value()
+            }));
+            global.set_initializer(&self.i8ptr_type.const_array(&func_ptrs));
+        }
+    }
+
+    // Generate the method tables of the interfaces and the list of them for
+    // each class (indexed by the interface)
+    fn gen_itables(&self) {
+        for (class_fullname, itables) in self.itables.iter() {
+            let itable_ptrs = itables
                 .iter()
-                .map(|name| {
-                    let func = self
-                        .get_llvm_func(&name.full_name)
-                        .as_any_value_enum()
-                        .into_pointer_value();
-                    self.builder
-                        .build_bitcast(func, self.i8ptr_type, "")
-                        .into_pointer_value()
+                .map(|opt_itable| match opt_itable {
+                    Some(itable) => {
+                        let global = self.gen_itable(class_fullname, itable);
+                        self.builder
+                            .build_bitcast(global.as_pointer_value(), self.i8ptr_type, "")
+                            .into_pointer_value()
+                    }
+                    None => self.i8ptr_type.const_null(),
                 })
                 .collect::<Vec<_>>();
-            global.set_initializer(&self.i8ptr_type.const_array(&func_ptrs));
+            let ary_type = self.i8ptr_type.array_type(itable_ptrs.len() as u32);
+            let global = self
+                .module
+                .add_global(ary_type, None, &llvm_itables_name(class_fullname));
+            global.set_constant(true);
+            global.set_linkage(inkwell::module::Linkage::Internal);
+            global.set_initializer(&self.i8ptr_type.const_array(&itable_ptrs));
         }
     }
 
+    // Generate the method table of an interface for a class
+    fn gen_itable(
+        &self,
+        class_fullname: &ClassFullname,
+        itable: &mir::itable::ITable,
+    ) -> inkwell::values::GlobalValue<'ictx> {
+        let ary_type = self.i8ptr_type.array_type(itable.fullnames.len() as u32);
+        let global = self.module.add_global(
+            ary_type,
+            None,
+            &llvm_itable_name(&itable.iface_fullname, class_fullname),
+        );
+        global.set_constant(true);
+        global.set_linkage(inkwell::module::Linkage::Internal);
+        let func_ptrs = itable
+            .fullnames
+            .iter()
+            .map(|name| {
+                let func = self
+                    .get_llvm_func(&name.full_name)
+                    .as_any_value_enum()
+                    .into_pointer_value();
+                self.builder
+                    .build_bitcast(func, self.i8ptr_type, "")
+                    .into_pointer_value()
+            })
+            .collect::<Vec<_>>();
+        global.set_initializer(&self.i8ptr_type.const_array(&func_ptrs));
+        global
+    }
+
     #[allow(clippy::ptr_arg)]
     fn gen_user_main(
         &mut self,
@@ -253,20 +329,21 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
 
         // Set fields for ivars
         let vt = self.llvm_vtable_ref_type().into();
+        let cls = self.llvm_class_ref_type().into();
         for (name, class) in classes {
             let struct_type = self.llvm_struct_types.get(name).unwrap();
             match name.0.as_str() {
                 "Int" => {
-                    struct_type.set_body(&[vt, self.i64_type.into()], false);
+                    struct_type.set_body(&[vt, cls, self.i64_type.into()], false);
                 }
                 "Float" => {
-                    struct_type.set_body(&[vt, self.f64_type.into()], false);
+                    struct_type.set_body(&[vt, cls, self.f64_type.into()], false);
                 }
                 "Bool" => {
-                    struct_type.set_body(&[vt, self.i1_type.into()], false);
+                    struct_type.set_body(&[vt, cls, self.i1_type.into()], false);
                 }
                 "Shiika::Internal::Ptr" => {
-                    struct_type.set_body(&[vt, self.i8ptr_type.into()], false);
+                    struct_type.set_body(&[vt, cls, self.i8ptr_type.into()], false);
                 }
                 _ => {
                     struct_type.set_body(&self.llvm_field_types(&class.ivars), false);
@@ -287,6 +364,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             .map(|ivar| self.llvm_type(&ivar.ty))
             .collect::<Vec<_>>();
         types.insert(0, self.llvm_vtable_ref_type().into());
+        types.insert(1, self.llvm_class_ref_type().into());
         types
     }
 
@@ -527,12 +605,25 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         exprs: &'hir HirExpressions,
         lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
     ) -> Result<(), Error> {
+        let is_method = function_origin == FunctionOrigin::Method;
         let (end_block, mut ctx) =
             self.new_ctx(function_origin, function, function_params, lvars);
+        if is_method {
+            self.gen_push_frame(&function.get_name().to_string_lossy());
+        }
         let last_value = self.gen_exprs(&mut ctx, exprs)?;
         let ret_block = if exprs.ty.is_never_type() {
             if ret_ty.is_never_type() {
                 self.builder.build_unconditional_branch(*end_block);
+            } else if self
+                .builder
+                .get_insert_block()
+                .unwrap()
+                .get_terminator()
+                .is_none()
+            {
+                // eg. the body ends with `raise`
+                self.builder.build_unreachable();
             }
             None
         } else {
@@ -544,6 +635,9 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         };
 
         self.builder.position_at_end(*end_block);
+        if is_method {
+            self.gen_pop_frame();
+        }
 
         if ret_ty.is_never_type() {
             // `Never` does not have an instance
@@ -570,6 +664,11 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         self.i8ptr_type
     }
 
+    /// LLVM type of a reference to a class object
+    fn llvm_class_ref_type(&self) -> inkwell::types::PointerType {
+        self.i8ptr_type
+    }
+
     /// Generate body of `.new`
     pub fn gen_body_of_new(
         &self,
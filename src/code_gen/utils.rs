@@ -1,13 +1,20 @@
 /// Provides utility functions used by code_gen/*.rs
 /// (some are also used by corelib/*.rs)
 use crate::code_gen::*;
+use crate::ty;
 use inkwell::types::*;
 use inkwell::AddressSpace;
 
 /// Number of elements before ivars
-const OBJ_HEADER_SIZE: usize = 1;
+const OBJ_HEADER_SIZE: usize = 2;
 /// 0th: reference to vtable
 const OBJ_VTABLE_IDX: usize = 0;
+/// 1st: reference to the constant which holds the class object
+const OBJ_CLASS_IDX: usize = 1;
+
+/// Number of the elements of a vtable before the methods
+/// 0th: reference to the itables of the class
+pub(super) const VTABLE_HEADER_SIZE: usize = 1;
 
 impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     /// Build IR to return ::Void
@@ -45,7 +52,9 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         size: usize,
     ) -> inkwell::values::BasicValueEnum<'run> {
         let vtable_ref = self.build_llvm_struct_ref(object, OBJ_VTABLE_IDX, "vtable_ref");
-        let ary_type = self.i8ptr_type.array_type(size as u32);
+        let ary_type = self
+            .i8ptr_type
+            .array_type((VTABLE_HEADER_SIZE + size) as u32);
         let vtable_ptr = self
             .builder
             .build_bitcast(
@@ -59,7 +68,64 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .build_load(vtable_ptr, "vtable")
             .into_array_value();
         self.builder
-            .build_extract_value(vtable, idx as u32, "func_raw")
+            .build_extract_value(vtable, (VTABLE_HEADER_SIZE + idx) as u32, "func_raw")
+            .unwrap()
+    }
+
+    /// Load the function at `idx` from the itable of the `iface_idx`th
+    /// interface for the class of `object`.
+    /// The itable is found by the reference stored in the vtable
+    pub fn build_itable_ref(
+        &self,
+        object: inkwell::values::BasicValueEnum<'run>,
+        iface_idx: usize,
+        idx: usize,
+        size: usize,
+    ) -> inkwell::values::BasicValueEnum<'run> {
+        let vtable_ref = self.build_llvm_struct_ref(object, OBJ_VTABLE_IDX, "vtable_ref");
+        let vtable_ptr = self
+            .builder
+            .build_bitcast(
+                vtable_ref,
+                self.i8ptr_type.ptr_type(AddressSpace::Generic),
+                "vtable_ptr",
+            )
+            .into_pointer_value();
+        let itables_ref = self.builder.build_load(vtable_ptr, "itables_ref");
+        let ary_type = self
+            .i8ptr_type
+            .array_type(self.itables.n_interfaces() as u32);
+        let itables_ptr = self
+            .builder
+            .build_bitcast(
+                itables_ref,
+                ary_type.ptr_type(AddressSpace::Generic),
+                "itables_ptr",
+            )
+            .into_pointer_value();
+        let itables = self
+            .builder
+            .build_load(itables_ptr, "itables")
+            .into_array_value();
+        let itable_ref = self
+            .builder
+            .build_extract_value(itables, iface_idx as u32, "itable_ref")
+            .unwrap();
+        let ary_type = self.i8ptr_type.array_type(size as u32);
+        let itable_ptr = self
+            .builder
+            .build_bitcast(
+                itable_ref,
+                ary_type.ptr_type(AddressSpace::Generic),
+                "itable_ptr",
+            )
+            .into_pointer_value();
+        let itable = self
+            .builder
+            .build_load(itable_ptr, "itable")
+            .into_array_value();
+        self.builder
+            .build_extract_value(itable, idx as u32, "func_raw")
             .unwrap()
     }
 
@@ -80,6 +146,81 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.build_llvm_struct_set(&object, OBJ_VTABLE_IDX, vtable, "vtable")
     }
 
+    /// Store reference to the class object into an object.
+    /// Since class objects are created at runtime, this stores the address
+    /// of the constant which holds it
+    fn build_store_class_ref<'a>(
+        &'a self,
+        object: inkwell::values::BasicValueEnum<'a>,
+        class_fullname: &ClassFullname,
+    ) {
+        let const_name = if class_fullname.is_meta() {
+            const_fullname("::Class")
+        } else {
+            self.sk_classes
+                .get(class_fullname)
+                .unwrap_or_else(|| panic!("[BUG] class `{}' not found", class_fullname))
+                .class_object_const()
+        };
+        let const_ref = self
+            .module
+            .get_global(&const_name.0)
+            .unwrap_or_else(|| panic!("[BUG] constant `{}' not found", const_name))
+            .as_pointer_value();
+        let class_ref = self
+            .builder
+            .build_bitcast(const_ref, self.i8ptr_type, "class_ref");
+        self.build_llvm_struct_set(&object, OBJ_CLASS_IDX, class_ref, "class_ref")
+    }
+
+    /// Load the class object of an object
+    pub fn build_class_object_load(
+        &self,
+        object: inkwell::values::BasicValueEnum<'run>,
+    ) -> inkwell::values::BasicValueEnum<'run> {
+        let class_ref = self.build_llvm_struct_ref(object, OBJ_CLASS_IDX, "class_ref");
+        let const_ptr = self
+            .builder
+            .build_bitcast(
+                class_ref,
+                self.llvm_type(&ty::class()).ptr_type(AddressSpace::Generic),
+                "const_ptr",
+            )
+            .into_pointer_value();
+        self.builder.build_load(const_ptr, "class_obj")
+    }
+
+    /// Build IR to check if the class of `object` is one of `classes`
+    pub fn build_is_instance_of(
+        &self,
+        object: inkwell::values::BasicValueEnum<'run>,
+        classes: &[ClassFullname],
+    ) -> inkwell::values::IntValue<'run> {
+        let vtable_ref = self
+            .build_llvm_struct_ref(object, OBJ_VTABLE_IDX, "vtable_ref")
+            .into_pointer_value();
+        let mut result = self.i1_type.const_int(0, false);
+        for name in classes {
+            let vtable = self
+                .module
+                .get_global(&llvm_vtable_name(name))
+                .unwrap_or_else(|| panic!("[BUG] vtable of `{}' not found", name))
+                .as_pointer_value();
+            let vtable = self
+                .builder
+                .build_bitcast(vtable, self.i8ptr_type, "vtable")
+                .into_pointer_value();
+            let eq = self.builder.build_int_compare(
+                inkwell::IntPredicate::EQ,
+                vtable_ref,
+                vtable,
+                "is_instance",
+            );
+            result = self.builder.build_or(result, eq, "is_instance");
+        }
+        result
+    }
+
     /// Load value of nth element of llvm struct
     fn build_llvm_struct_ref(
         &self,
@@ -151,8 +292,9 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         // %foo = bitcast i8* %mem to %#{t}*",
         let obj = self.builder.build_bitcast(raw_addr, obj_ptr_type, reg_name);
 
-        // Store reference to vtable
+        // Store reference to vtable and the class object
         self.build_store_vtable(obj, &class_fullname);
+        self.build_store_class_ref(obj, &class_fullname);
 
         obj
     }
@@ -167,7 +309,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     pub fn llvm_type(&self, ty: &TermTy) -> inkwell::types::BasicTypeEnum<'ictx> {
         let s = match &ty.body {
             TyBody::TySpe { base_name, .. } => &base_name,
-            TyBody::TyParamRef { .. } => "Object", // its upper bound
+            TyBody::TyParamRef { .. } => "Object", // Type parameters are erased
             _ => &ty.fullname.0,
         };
         self.llvm_struct_type(&class_fullname(s))
@@ -195,3 +337,13 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
 pub(super) fn llvm_vtable_name(classname: &ClassFullname) -> String {
     format!("vtable_{}", classname)
 }
+
+/// Name of llvm constant of an itable
+pub(super) fn llvm_itable_name(iface: &ClassFullname, classname: &ClassFullname) -> String {
+    format!("itable_{}_{}", iface, classname)
+}
+
+/// Name of llvm constant of the list of the itables of a class
+pub(super) fn llvm_itables_name(classname: &ClassFullname) -> String {
+    format!("itables_{}", classname)
+}
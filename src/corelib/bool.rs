@@ -1,6 +1,30 @@
+use crate::corelib::create_method;
 use crate::hir::*;
-//use crate::corelib::create_method;
+use inkwell::IntPredicate;
 
 pub fn create_methods() -> Vec<SkMethod> {
-    vec![]
+    vec![
+        create_method("Bool", "==(other: Bool) -> Bool", |code_gen, function| {
+            let this = function.get_params()[0];
+            let val1 = code_gen.unbox_bool(this);
+            let that = function.get_params()[1];
+            let val2 = code_gen.unbox_bool(that);
+            let result = code_gen
+                .builder
+                .build_int_compare(IntPredicate::EQ, val1, val2, "eq");
+            let sk_result = code_gen.box_bool(result);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        }),
+        create_method("Bool", "hash -> Int", |code_gen, function| {
+            let this = function.get_params()[0];
+            let b = code_gen.unbox_bool(this);
+            let int = code_gen
+                .builder
+                .build_int_z_extend(b, code_gen.i64_type, "int");
+            let sk_int = code_gen.box_int(&int);
+            code_gen.builder.build_return(Some(&sk_int));
+            Ok(())
+        }),
+    ]
 }
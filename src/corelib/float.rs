@@ -194,6 +194,20 @@ pub fn create_methods() -> Vec<SkMethod> {
             code_gen.builder.build_return(Some(&sk_result));
             Ok(())
         }),
+        create_method("Float", "hash -> Int", |code_gen, function| {
+            let this = function.get_params()[0];
+            let float = code_gen.unbox_float(this);
+            // Adding 0.0 turns -0.0 into 0.0 so that they have the same hash
+            let zero = code_gen.f64_type.const_float(0.0);
+            let normalized = code_gen.builder.build_float_add(float, zero, "normalized");
+            let bits = code_gen
+                .builder
+                .build_bitcast(normalized, code_gen.i64_type, "bits")
+                .into_int_value();
+            let sk_int = code_gen.box_int(&bits);
+            code_gen.builder.build_return(Some(&sk_int));
+            Ok(())
+        }),
         create_method("Float", "to_i() -> Int", |code_gen, function| {
             let this = function.get_params()[0];
             let float = code_gen.unbox_float(this);
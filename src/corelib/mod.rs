@@ -9,6 +9,7 @@ mod never;
 mod object;
 mod shiika_internal_memory;
 mod shiika_internal_ptr;
+pub mod tuple;
 mod void;
 use crate::hir::*;
 use crate::names::*;
@@ -179,7 +180,7 @@ fn make_classes(
                 fullname: class_fullname(&name),
                 typarams: typarams
                     .iter()
-                    .map(|s| ty::TyParam { name: s.clone() })
+                    .map(|s| ty::TyParam::new(s.clone()))
                     .collect(),
                 superclass_fullname: super_name,
                 instance_ty: ty::raw(&name),
@@ -189,6 +190,9 @@ fn make_classes(
                     .map(|x| (x.signature.first_name().clone(), x.signature.clone()))
                     .collect(),
                 const_is_obj: (name == "Void"),
+                interfaces: vec![],
+                is_interface: false,
+                is_enum: false,
             },
         );
         sk_methods.insert(class_fullname(&name), imethods);
@@ -201,10 +205,7 @@ fn make_classes(
                 metaclass_fullname(&name),
                 SkClass {
                     fullname: metaclass_fullname(&name),
-                    typarams: typarams
-                        .into_iter()
-                        .map(|s| ty::TyParam { name: s })
-                        .collect(),
+                    typarams: typarams.into_iter().map(ty::TyParam::new).collect(),
                     superclass_fullname: Some(class_fullname("Class")),
                     instance_ty: ty::meta(&name),
                     ivars: meta_ivars,
@@ -213,6 +214,9 @@ fn make_classes(
                         .map(|x| (x.signature.first_name().clone(), x.signature.clone()))
                         .collect(),
                     const_is_obj: false,
+                    interfaces: vec![],
+                    is_interface: false,
+                    is_enum: false,
                 },
             );
             sk_methods.insert(metaclass_fullname(&name), cmethods);
@@ -234,11 +238,12 @@ fn create_method_generic(
     let mut parser = parser::Parser::new_with_state(sig_str, parser::lexer::LexerState::MethodName);
     let (ast_sig, _) = parser.parse_method_signature().unwrap();
     parser.expect_eof().unwrap();
-    let sig = crate::hir::signature::create_signature(
-        &class_fullname(class_name),
-        &ast_sig,
-        typaram_names,
-    );
+    let typarams = typaram_names
+        .iter()
+        .map(|s| ty::TyParam::new(s.clone()))
+        .collect::<Vec<_>>();
+    let sig =
+        crate::hir::signature::create_signature(&class_fullname(class_name), &ast_sig, &typarams);
 
     SkMethod {
         signature: sig,
@@ -1,3 +1,4 @@
+use crate::code_gen::CodeGen;
 use crate::corelib::create_method;
 use crate::hir::*;
 use inkwell::values::*;
@@ -8,6 +9,39 @@ pub fn create_methods() -> Vec<SkMethod> {
             code_gen.build_return_void();
             Ok(())
         }),
+        create_method("Object", "class -> Class", |code_gen, function| {
+            let this = function.get_params()[0];
+            let cls_obj = code_gen.build_class_object_load(this);
+            code_gen.builder.build_return(Some(&cls_obj));
+            Ok(())
+        }),
+        create_method(
+            "Object",
+            "==(other: Object) -> Bool",
+            |code_gen, function| {
+                let result = build_identity_check(code_gen, function);
+                let sk_result = code_gen.box_bool(result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            },
+        ),
+        create_method(
+            "Object",
+            "equal(other: Object) -> Bool",
+            |code_gen, function| {
+                let result = build_identity_check(code_gen, function);
+                let sk_result = code_gen.box_bool(result);
+                code_gen.builder.build_return(Some(&sk_result));
+                Ok(())
+            },
+        ),
+        create_method("Object", "hash -> Int", |code_gen, function| {
+            let this = function.get_params()[0];
+            let addr = build_address(code_gen, this);
+            let sk_result = code_gen.box_int(&addr);
+            code_gen.builder.build_return(Some(&sk_result));
+            Ok(())
+        }),
         create_method("Object", "putd(n: Int) -> Void", |code_gen, function| {
             let sk_int = function.get_params()[1];
             let n = code_gen.unbox_int(sk_int);
@@ -78,3 +112,19 @@ pub fn create_methods() -> Vec<SkMethod> {
         ),
     ]
 }
+
+/// Return true if the receiver and the argument are the same object
+fn build_identity_check<'a>(code_gen: &'a CodeGen, function: &FunctionValue<'a>) -> IntValue<'a> {
+    let this = build_address(code_gen, function.get_params()[0]);
+    let that = build_address(code_gen, function.get_params()[1]);
+    code_gen
+        .builder
+        .build_int_compare(inkwell::IntPredicate::EQ, this, that, "eq")
+}
+
+/// Return the address of an object as an integer
+fn build_address<'a>(code_gen: &'a CodeGen, obj: BasicValueEnum<'a>) -> IntValue<'a> {
+    code_gen
+        .builder
+        .build_ptr_to_int(obj.into_pointer_value(), code_gen.i64_type, "addr")
+}
@@ -0,0 +1,68 @@
+//! Source of the tuple classes `Tuple2`, ..., `TupleN` (N = `ty::MAX_TUPLE_SIZE`).
+//! They are generated here rather than written in builtin/ because they
+//! differ only in the number of the elements.
+use crate::ty;
+
+/// Return the Shiika source which defines the tuple classes
+pub fn tuples_src() -> String {
+    let mut src = String::new();
+    src.push_str("# Tuples (eg. `(1, \"a\")` is a `Tuple2<Int, String>`.)\n");
+    src.push_str("# The elements can be accessed with `.0`, `.1`, etc.\n");
+    for n in 2..=ty::MAX_TUPLE_SIZE {
+        src.push('\n');
+        src.push_str(&tuple_class_src(n));
+    }
+    src
+}
+
+/// Return the definition of the class of the tuples with `n` elements
+fn tuple_class_src(n: usize) -> String {
+    let name = ty::tuple_class_name(n);
+    let typarams = join(n, ", ", |i| format!("T{}", i + 1));
+    let literal_typarams = join(n, ", ", |i| format!("U{}", i + 1));
+    format!(
+        "class {name}<{typarams}>
+  # Create a tuple (used for tuple literals `({literal_example})`)
+  def self.literal<{literal_typarams}>({literal_params}) -> {name}<{literal_typarams}>
+    {name}<{literal_typarams}>.new({literal_args})
+  end
+
+  def initialize({iparams}); end
+
+  # Return true if each element of `other` is equal to the one of `self`
+  # (compared by `==`.)
+  def ==(other: {name}<{typarams}>) -> Bool
+    {eq}
+  end
+
+  # Return hash value of `self` (computed from the elements)
+  def hash -> Int
+    var h = @0.hash
+{hash}    h
+  end
+
+  # Return string representation of `self` (for debugging)
+  def inspect -> String
+    \"(\" + {inspect} + \")\"
+  end
+end
+",
+        name = name,
+        typarams = typarams,
+        literal_typarams = literal_typarams,
+        literal_example = join(n, ", ", |i| ((b'a' + i as u8) as char).to_string()),
+        literal_params = join(n, ", ", |i| format!("v{}: U{}", i + 1, i + 1)),
+        literal_args = join(n, ", ", |i| format!("v{}", i + 1)),
+        iparams = join(n, ", ", |i| format!("@{}: T{}", i, i + 1)),
+        eq = join(n, " && ", |i| format!("@{} == other.{}", i, i)),
+        hash = (1..n)
+            .map(|i| format!("    h = h * 31 + @{}.hash\n", i))
+            .collect::<String>(),
+        inspect = join(n, " + \", \" + ", |i| format!("@{}.inspect", i)),
+    )
+}
+
+/// Join the strings `f(0)`, ..., `f(n - 1)` with `sep`
+fn join(n: usize, sep: &str, f: impl Fn(usize) -> String) -> String {
+    (0..n).map(f).collect::<Vec<_>>().join(sep)
+}
@@ -0,0 +1,144 @@
+//! Render compile errors for users, like this:
+//!
+//! ```text
+//! error[E0003]: type error: the argument `x' of `Foo#bar' should be Int but got String
+//!  --> foo.sk:3:9
+//!   |
+//! 3 | foo.bar("a")
+//!   |         ^^^
+//!   = help: ...
+//! ```
+use crate::error::{Error, ErrorDetails, ErrorNote};
+use crate::location::LocationSpan;
+use std::fs;
+
+/// Render `err` reading the source snippet from the file where the error is
+pub fn render(err: &Error) -> String {
+    if let ErrorDetails::Multiple(errs) = &err.details {
+        return render_multiple(errs, render);
+    }
+    render_error(err, read_source(err).as_deref())
+}
+
+/// Render `err` with the given source text of the file `filepath`.
+/// The snippets of the errors in the other files are read from the files
+pub fn render_with_source(err: &Error, filepath: &str, src: Option<&str>) -> String {
+    if let ErrorDetails::Multiple(errs) = &err.details {
+        return render_multiple(errs, |e| render_with_source(e, filepath, src));
+    }
+    match &err.span {
+        Some(span) if span.filepath.as_str() != filepath => {
+            render_error(err, read_source(err).as_deref())
+        }
+        _ => render_error(err, src),
+    }
+}
+
+/// Read the source text of the file where the error is
+fn read_source(err: &Error) -> Option<String> {
+    err.span
+        .as_ref()
+        .and_then(|span| fs::read_to_string(span.filepath.as_str()).ok())
+}
+
+/// Render a (non-multiple) error with the source text of its file
+fn render_error(err: &Error, src: Option<&str>) -> String {
+    let mut out = format!(
+        "error[{}]: {}: {}\n",
+        err.details.code(),
+        err.details.title(),
+        err.msg
+    );
+    let gutter = match &err.span {
+        Some(span) => (span.begin.line + 1).to_string().len(),
+        None => 0,
+    };
+    if let Some(span) = &err.span {
+        out.push_str(&format!("{}--> {}\n", " ".repeat(gutter), span));
+        if let Some(line) = src.and_then(|s| s.lines().nth(span.begin.line)) {
+            out.push_str(&render_snippet(span, line, gutter));
+        }
+    }
+    if let Some(source) = &err.source {
+        out.push_str(&format!(
+            "{} = note: caused by: {}\n",
+            " ".repeat(gutter),
+            source
+        ));
+    }
+    for note in &err.notes {
+        let (label, msg) = match note {
+            ErrorNote::Note(msg) => ("note", msg),
+            ErrorNote::Help(msg) => ("help", msg),
+        };
+        out.push_str(&format!("{} = {}: {}\n", " ".repeat(gutter), label, msg));
+    }
+    out
+}
+
+/// Render each error and the number of them
+fn render_multiple(errs: &[Error], f: impl Fn(&Error) -> String) -> String {
+    let mut out = errs.iter().map(f).collect::<Vec<_>>().join("\n");
+    out.push_str(&format!(
+        "\nerror: aborting due to {} previous errors\n",
+        errs.len()
+    ));
+    out
+}
+
+/// Show the source line and underline the span
+fn render_snippet(span: &LocationSpan, line: &str, gutter: usize) -> String {
+    let line_len = line.chars().count();
+    let begin = span.begin.col.min(line_len);
+    let end = if span.end.line == span.begin.line {
+        span.end.col.min(line_len)
+    } else {
+        // Only the first line is shown
+        line_len
+    };
+    let width = if end > begin { end - begin } else { 1 };
+    // Keep tabs so that the carets are aligned with the source
+    let indent = line
+        .chars()
+        .take(begin)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    let blank = " ".repeat(gutter);
+    format!(
+        "{blank} |\n{lineno} | {line}\n{blank} | {indent}{carets}\n",
+        blank = blank,
+        lineno = span.begin.line + 1,
+        line = line,
+        indent = indent,
+        carets = "^".repeat(width)
+    )
+}
+
+/// Returns true if the compiler backtrace should be shown.
+/// It is only useful for debugging the compiler itself.
+pub fn show_backtrace(err: &Error) -> bool {
+    match &err.details {
+        ErrorDetails::Bug => true,
+        ErrorDetails::Multiple(errs) => errs.iter().any(show_backtrace),
+        _ => std::env::var("SHIIKA_DEBUG").is_ok(),
+    }
+}
+
+/// Render the frames of the compiler backtrace
+pub fn render_backtrace(err: &Error) -> String {
+    if let ErrorDetails::Multiple(errs) = &err.details {
+        return errs.iter().map(render_backtrace).collect();
+    }
+    let mut out = String::new();
+    for frame in err.backtrace.frames() {
+        for symbol in frame.symbols() {
+            if let Some(name) = symbol.name() {
+                let s = format!("{}", name);
+                if s.starts_with("shiika") {
+                    out.push_str(&format!("- {}\n", s));
+                }
+            }
+        }
+    }
+    out
+}
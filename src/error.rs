@@ -1,3 +1,4 @@
+use crate::location::LocationSpan;
 use backtrace::Backtrace;
 
 #[derive(Debug)]
@@ -6,11 +7,25 @@ pub struct Error {
     pub backtrace: Backtrace,
     pub details: ErrorDetails,
     pub source: Option<Box<dyn std::error::Error>>,
+    /// Where in the source the error is found (if known)
+    pub span: Option<LocationSpan>,
+    /// Additional messages shown after the source snippet
+    pub notes: Vec<ErrorNote>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorNote {
+    /// Supplementary information (eg. where the conflicting definition is)
+    Note(String),
+    /// Suggestion to fix the error
+    Help(String),
 }
 #[derive(Debug)]
 pub enum ErrorDetails {
     // Error on parsing
     ParseError {
+        /// Empty if not parsing a file
+        filepath: String,
         location: crate::parser::lexer::Cursor,
     },
     // Parsing is succeeded but syntactically wrong
@@ -25,6 +40,8 @@ pub enum ErrorDetails {
     RunnerError,
     // Not an user-error
     Bug,
+    // Errors found in a compilation (created by `error::errors`)
+    Multiple(Vec<Error>),
 }
 
 impl std::fmt::Display for Error {
@@ -34,12 +51,67 @@ impl std::fmt::Display for Error {
 }
 impl std::error::Error for Error {}
 
+impl Error {
+    /// Set the location of this error unless it is already known
+    /// (the innermost one is the most accurate)
+    pub fn located_at(mut self, span: &Option<LocationSpan>) -> Error {
+        if self.span.is_none() {
+            self.span = span.clone();
+        }
+        self
+    }
+
+    /// Add a note to this error
+    pub fn with_note(mut self, msg: impl Into<String>) -> Error {
+        self.notes.push(ErrorNote::Note(msg.into()));
+        self
+    }
+
+    /// Add a suggestion to this error
+    pub fn with_help(mut self, msg: impl Into<String>) -> Error {
+        self.notes.push(ErrorNote::Help(msg.into()));
+        self
+    }
+}
+
+impl ErrorDetails {
+    /// Error code shown in the diagnostics (eg. `error[E0003]`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorDetails::ParseError { .. } => "E0001",
+            ErrorDetails::SyntaxError => "E0002",
+            ErrorDetails::TypeError => "E0003",
+            ErrorDetails::NameError => "E0004",
+            ErrorDetails::ProgramError => "E0005",
+            ErrorDetails::RunnerError => "E0006",
+            ErrorDetails::Bug => "B0001",
+            ErrorDetails::Multiple(_) => "E0000",
+        }
+    }
+
+    /// Short description of this kind of error
+    pub fn title(&self) -> &'static str {
+        match self {
+            ErrorDetails::ParseError { .. } => "parse error",
+            ErrorDetails::SyntaxError => "syntax error",
+            ErrorDetails::TypeError => "type error",
+            ErrorDetails::NameError => "name error",
+            ErrorDetails::ProgramError => "program error",
+            ErrorDetails::RunnerError => "runner error",
+            ErrorDetails::Bug => "internal compiler error",
+            ErrorDetails::Multiple(_) => "multiple errors",
+        }
+    }
+}
+
 pub fn syntax_error(msg: &str) -> Error {
     Error {
         msg: msg.to_string(),
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::SyntaxError,
         source: None,
+        span: None,
+        notes: vec![],
     }
 }
 
@@ -49,6 +121,8 @@ pub fn type_error(msg: &str) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::TypeError,
         source: None,
+        span: None,
+        notes: vec![],
     }
 }
 
@@ -58,6 +132,8 @@ pub fn name_error(msg: &str) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::NameError,
         source: None,
+        span: None,
+        notes: vec![],
     }
 }
 
@@ -67,6 +143,8 @@ pub fn program_error(msg: &str) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::ProgramError,
         source: None,
+        span: None,
+        notes: vec![],
     }
 }
 
@@ -76,6 +154,8 @@ pub fn runner_error(msg: impl Into<String>, source: Box<dyn std::error::Error>)
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::RunnerError,
         source: Some(source),
+        span: None,
+        notes: vec![],
     }
 }
 
@@ -85,6 +165,8 @@ pub fn plain_runner_error(msg: impl Into<String>) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::RunnerError,
         source: None,
+        span: None,
+        notes: vec![],
     }
 }
 
@@ -94,6 +176,30 @@ pub fn bug(msg: impl Into<String>) -> Error {
         backtrace: backtrace::Backtrace::new(),
         details: ErrorDetails::Bug,
         source: None,
+        span: None,
+        notes: vec![],
+    }
+}
+
+/// Combine the errors into one. Returns the error as-is if there is only one
+pub fn errors(errs: Vec<Error>) -> Error {
+    let mut flat = vec![];
+    for err in errs {
+        match err.details {
+            ErrorDetails::Multiple(inner) => flat.extend(inner),
+            _ => flat.push(err),
+        }
+    }
+    if flat.len() == 1 {
+        return flat.pop().unwrap();
+    }
+    Error {
+        msg: format!("{} errors found", flat.len()),
+        backtrace: backtrace::Backtrace::new(),
+        details: ErrorDetails::Multiple(flat),
+        source: None,
+        span: None,
+        notes: vec![],
     }
 }
 
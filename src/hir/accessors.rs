@@ -74,6 +74,8 @@ fn create_setter(clsname: &ClassFullname, ivar: &SkIVar) -> SkMethod {
         params: vec![MethodParam {
             name: ivar.accessor_name(),
             ty: ivar.ty.clone(),
+            default_expr: None,
+            is_rest: false,
         }],
         typarams: vec![],
     };
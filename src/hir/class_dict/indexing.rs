@@ -50,36 +50,90 @@ impl ClassDict {
                 ivars: c.ivars,
                 method_sigs: c.method_sigs,
                 const_is_obj: c.const_is_obj,
+                interfaces: c.interfaces,
+                is_interface: c.is_interface,
+                is_enum: c.is_enum,
             })
         });
     }
 
     pub fn index_program(&mut self, toplevel_defs: &[&ast::Definition]) -> Result<(), Error> {
-        toplevel_defs.iter().try_for_each(|def| match def {
+        // Go on after an error so that all the errors are reported at once
+        let mut errs = vec![];
+        for def in toplevel_defs {
+            if let Err(e) = self.index_toplevel_def(def) {
+                errs.push(e.located_at(&def.span()));
+            }
+        }
+        if let Err(e) = self.check_interfaces() {
+            errs.push(e);
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(error::errors(errs))
+        }
+    }
+
+    fn index_toplevel_def(&mut self, def: &ast::Definition) -> Result<(), Error> {
+        match def {
             ast::Definition::ClassDefinition {
                 name,
                 typarams,
                 super_name,
+                interfaces,
                 defs,
+                ..
             } => {
-                self.index_class(&name.add_namespace(""), &typarams, &super_name, &defs)?;
+                let full = name.add_namespace("");
+                self.index_class(&full, &typarams, &super_name, &interfaces, &defs)?;
                 Ok(())
             }
+            ast::Definition::EnumDefinition {
+                name,
+                typarams,
+                cases,
+                defs,
+                ..
+            } => self.index_enum(&name.add_namespace(""), &typarams, &cases, &defs),
+            ast::Definition::ModuleDefinition { name, defs, .. } => {
+                self.index_module(&name.add_namespace(""), &defs)
+            }
+            ast::Definition::InterfaceDefinition {
+                name,
+                typarams,
+                sigs,
+                ..
+            } => self.index_interface(&name.add_namespace(""), &typarams, &sigs),
             ast::Definition::ConstDefinition { .. } => Ok(()),
             _ => Err(error::syntax_error(&format!(
                 "must not be toplevel: {:?}",
                 def
             ))),
-        })
+        }
     }
 
     fn index_class(
         &mut self,
         fullname: &ClassFullname,
-        typarams: &[String],
+        typarams: &[ast::AstTyParam],
         super_name: &ClassFullname,
+        interfaces: &[ast::Typ],
         defs: &[ast::Definition],
     ) -> Result<(), Error> {
+        let typarams = &signature::convert_typarams(typarams);
+        let mut super_name = self.resolve_class_name(fullname, super_name);
+        let mut interfaces = interfaces
+            .iter()
+            .map(|typ| self.convert_interface_typ(fullname, typ, typarams))
+            .collect::<Vec<_>>();
+        // eg. `class A : Comparable` (the superclass is omitted)
+        if self.is_interface(&super_name) {
+            interfaces.insert(0, ty::raw(&super_name.0));
+            super_name = class_fullname("Object");
+        }
+        self.check_declared_interfaces(&interfaces)?;
+        let super_name = &super_name;
         let metaclass_fullname = fullname.meta_name();
         let new_sig = signature::signature_of_new(
             &metaclass_fullname,
@@ -96,11 +150,14 @@ impl ClassDict {
                 // Shiika will not support reopening a class but this is needed
                 // for classes defined both in src corelib/ and in builtin/.
                 class.method_sigs.extend(instance_methods);
+                // The upper bounds are given in builtin/ (eg. `Range<T : Discrete<T>>`)
+                class.typarams = typarams.to_vec();
                 let metaclass = self
                     .sk_classes
                     .get_mut(&metaclass_fullname)
                     .expect("[BUG] Only class is indexed");
                 metaclass.method_sigs.extend(class_methods);
+                metaclass.typarams = typarams.to_vec();
                 // Add `.new` to the metaclass
                 if !metaclass.method_sigs.contains_key(&method_firstname("new")) {
                     metaclass
@@ -117,13 +174,244 @@ impl ClassDict {
                 class_methods,
             )?,
         }
+        self.get_class_mut(fullname, "index_class")
+            .interfaces
+            .extend(interfaces);
+        Ok(())
+    }
+
+    /// Convert the type of an interface declared by a class
+    /// eg. `Comparable<A>` for `class A : Comparable<A>`,
+    ///     `Comparable<Pair<T>>` for `class Pair<T> : Comparable<Pair<T>>`
+    fn convert_interface_typ(
+        &self,
+        fullname: &ClassFullname,
+        typ: &ast::Typ,
+        typarams: &[TyParam],
+    ) -> TermTy {
+        let name = self.resolve_class_name(fullname, &class_fullname(&typ.name));
+        if typ.typ_args.is_empty() {
+            ty::raw(&name.0)
+        } else {
+            let tyargs = typ
+                .typ_args
+                .iter()
+                .map(|t| signature::convert_typ(t, typarams, &[]))
+                .collect();
+            ty::spe(&name.0, tyargs)
+        }
+    }
+
+    /// Return Err if the interfaces declared by a class are not valid
+    fn check_declared_interfaces(&self, interfaces: &[TermTy]) -> Result<(), Error> {
+        for (i, iface) in interfaces.iter().enumerate() {
+            let name = iface.vtable_name();
+            let n_tyargs = match &iface.body {
+                TyBody::TySpe { type_args, .. } => type_args.len(),
+                _ => 0,
+            };
+            match self.find_class(&name) {
+                Some(cls) if cls.is_interface => {
+                    if cls.typarams.len() != n_tyargs {
+                        return Err(error::type_error(&format!(
+                            "wrong number of type arguments for {}: expected {} but got {}",
+                            name,
+                            cls.typarams.len(),
+                            n_tyargs
+                        )));
+                    }
+                }
+                _ => return Err(error::name_error(&format!("unknown interface: {:?}", name))),
+            }
+            if interfaces[..i].iter().any(|t| t.vtable_name() == name) {
+                return Err(error::program_error(&format!(
+                    "{} is declared more than once",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the name of a superclass or an interface from the namespace
+    /// of the class
+    /// eg. `B` in `module M; class A : B` is `M::B` if it exists
+    fn resolve_class_name(
+        &self,
+        fullname: &ClassFullname,
+        super_name: &ClassFullname,
+    ) -> ClassFullname {
+        let namespace = fullname.0.rsplitn(2, "::").nth(1).unwrap_or("");
+        enclosing_namespaces(namespace)
+            .iter()
+            .map(|ns| class_firstname(&super_name.0).add_namespace(ns))
+            .find(|name| self.class_exists(&name.0))
+            .unwrap_or_else(|| super_name.clone())
+    }
+
+    /// Index a module.
+    /// A module is indexed as a class which has no instance methods and no `.new`
+    /// (its instance methods are copied into the classes by `hir::mixin`)
+    fn index_module(
+        &mut self,
+        fullname: &ClassFullname,
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        let (_, class_methods) = self.index_defs_in_class(fullname, &[], defs)?;
+        self.add_class(SkClass {
+            fullname: fullname.clone(),
+            typarams: vec![],
+            superclass_fullname: Some(class_fullname("Object")),
+            instance_ty: ty::raw(&fullname.0),
+            ivars: HashMap::new(),
+            method_sigs: HashMap::new(),
+            const_is_obj: false,
+            interfaces: vec![],
+            is_interface: false,
+            is_enum: false,
+        });
+        let the_class = self.get_class(&class_fullname("Class"), "index_module");
+        let meta_ivars = the_class.ivars.clone();
+        self.add_class(SkClass {
+            fullname: fullname.meta_name(),
+            typarams: vec![],
+            superclass_fullname: Some(class_fullname("Class")),
+            instance_ty: ty::meta(&fullname.0),
+            ivars: meta_ivars,
+            method_sigs: class_methods,
+            const_is_obj: false,
+            interfaces: vec![],
+            is_interface: false,
+            is_enum: false,
+        });
+        Ok(())
+    }
+
+    /// Index an interface.
+    /// An interface is indexed as a class which has only the signatures of
+    /// the required methods (and has no metaclass)
+    fn index_interface(
+        &mut self,
+        fullname: &ClassFullname,
+        typarams: &[ast::AstTyParam],
+        sigs: &[ast::AstMethodSignature],
+    ) -> Result<(), Error> {
+        if self.class_exists(&fullname.0) {
+            return Err(error::program_error(&format!(
+                "{} is already defined",
+                fullname
+            )));
+        }
+        let typarams = signature::convert_typarams(typarams);
+        let method_sigs = sigs
+            .iter()
+            .map(|sig| {
+                let hir_sig = signature::create_signature(&fullname, sig, &typarams);
+                (sig.name.clone(), hir_sig)
+            })
+            .collect();
+        self.add_class(SkClass {
+            fullname: fullname.clone(),
+            typarams,
+            superclass_fullname: Some(class_fullname("Object")),
+            instance_ty: ty::raw(&fullname.0),
+            ivars: HashMap::new(),
+            method_sigs,
+            const_is_obj: false,
+            interfaces: vec![],
+            is_interface: true,
+            is_enum: false,
+        });
+        Ok(())
+    }
+
+    /// Check that the classes have the methods required by the interfaces
+    /// they declare
+    fn check_interfaces(&self) -> Result<(), Error> {
+        let mut errs = vec![];
+        let mut classes = self
+            .sk_classes
+            .values()
+            .filter(|cls| !cls.interfaces.is_empty())
+            .collect::<Vec<_>>();
+        classes.sort_unstable_by(|a, b| a.fullname.0.cmp(&b.fullname.0));
+        for cls in classes {
+            for iface_ty in &cls.interfaces {
+                let iface_name = &iface_ty.vtable_name();
+                let iface = self.get_class(iface_name, "check_interfaces");
+                // eg. `<(other: T)` of `Comparable<T>` is `<(other: Int)` for `Comparable<Int>`
+                let tyargs = match &iface_ty.body {
+                    TyBody::TySpe { type_args, .. } => Some(&type_args[..]),
+                    _ => None,
+                };
+                let mut reqs = iface
+                    .method_sigs
+                    .values()
+                    .map(|sig| sig.specialize(tyargs, None))
+                    .collect::<Vec<_>>();
+                reqs.sort_unstable_by(|a, b| a.fullname.full_name.cmp(&b.fullname.full_name));
+                for req in reqs {
+                    let name = &req.fullname.first_name;
+                    let sig = match self.lookup_method(&cls.instance_ty, name, &[]) {
+                        Ok((sig, _)) => sig,
+                        Err(_) => {
+                            errs.push(error::program_error(&format!(
+                                "{} does not implement `{}' required by {}",
+                                cls.fullname, name, iface_name
+                            )));
+                            continue;
+                        }
+                    };
+                    let params_match = sig.params.len() == req.params.len()
+                        && sig
+                            .params
+                            .iter()
+                            .zip(req.params.iter())
+                            .all(|(p, q)| p.ty.equals_to(&q.ty));
+                    if !params_match || !sig.ret_ty.conforms_to(&req.ret_ty, self) {
+                        errs.push(error::type_error(&format!(
+                            "the signature of {} does not match {}",
+                            sig.fullname, req.fullname
+                        )));
+                    }
+                }
+            }
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(error::errors(errs))
+        }
+    }
+
+    /// Index an enum and its cases.
+    /// Each case is indexed as a subclass of the enum (eg. `Maybe::Some`)
+    fn index_enum(
+        &mut self,
+        fullname: &ClassFullname,
+        typarams: &[ast::AstTyParam],
+        cases: &[ast::EnumCase],
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        self.index_class(fullname, typarams, &class_fullname("Object"), &[], defs)?;
+        self.get_class_mut(fullname, "index_enum").is_enum = true;
+        for case in cases {
+            let case_fullname = case.name.add_namespace(&fullname.0);
+            let case_defs = case.initializer().into_iter().collect::<Vec<_>>();
+            self.index_class(&case_fullname, typarams, fullname, &[], &case_defs)?;
+            if case.params.is_empty() {
+                // The constant (eg. `::Maybe::None`) holds the instance
+                self.get_class_mut(&case_fullname, "index_enum")
+                    .const_is_obj = true;
+            }
+        }
         Ok(())
     }
 
     fn index_defs_in_class(
         &mut self,
         fullname: &ClassFullname,
-        typarams: &[String],
+        typarams: &[TyParam],
         defs: &[ast::Definition],
     ) -> Result<
         (
@@ -137,10 +425,13 @@ impl ClassDict {
         for def in defs {
             match def {
                 ast::Definition::InstanceMethodDefinition { sig, .. } => {
+                    check_not_final(&fullname, sig).map_err(|e| e.located_at(&def.span()))?;
                     let hir_sig = signature::create_signature(&fullname, sig, typarams);
                     instance_methods.insert(sig.name.clone(), hir_sig);
                 }
                 ast::Definition::ClassMethodDefinition { sig, .. } => {
+                    check_not_final(&fullname.meta_name(), sig)
+                        .map_err(|e| e.located_at(&def.span()))?;
                     let hir_sig = signature::create_signature(&fullname.meta_name(), sig, &[]);
                     class_methods.insert(sig.name.clone(), hir_sig);
                 }
@@ -149,10 +440,42 @@ impl ClassDict {
                     name,
                     typarams,
                     super_name,
+                    interfaces,
+                    defs,
+                    ..
+                } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.index_class(&full, &typarams, &super_name, &interfaces, &defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::EnumDefinition {
+                    name,
+                    typarams,
+                    cases,
                     defs,
+                    ..
                 } => {
                     let full = name.add_namespace(&fullname.0);
-                    self.index_class(&full, &typarams, &super_name, &defs)?;
+                    self.index_enum(&full, &typarams, &cases, &defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::ModuleDefinition { name, defs, .. } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.index_module(&full, &defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::InterfaceDefinition {
+                    name,
+                    typarams,
+                    sigs,
+                    ..
+                } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.index_interface(&full, &typarams, &sigs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::IncludeDefinition { .. } => {
+                    // Already expanded by hir::mixin
                 }
             }
         }
@@ -162,19 +485,12 @@ impl ClassDict {
     fn add_new_class(
         &mut self,
         fullname: &ClassFullname,
-        typaram_names: &[String],
+        typarams: &[TyParam],
         super_name: &ClassFullname,
         new_sig: MethodSignature,
         instance_methods: HashMap<MethodFirstname, MethodSignature>,
         mut class_methods: HashMap<MethodFirstname, MethodSignature>,
     ) -> Result<(), Error> {
-        let typarams = typaram_names
-            .iter()
-            .map(|s| TyParam {
-                name: s.to_string(),
-            })
-            .collect::<Vec<_>>();
-
         // Add `.new` to the metaclass
         class_methods.insert(new_sig.fullname.first_name.clone(), new_sig);
         if !self.class_exists(&super_name.0) {
@@ -186,12 +502,15 @@ impl ClassDict {
 
         self.add_class(SkClass {
             fullname: fullname.clone(),
-            typarams: typarams.clone(),
+            typarams: typarams.to_vec(),
             superclass_fullname: Some(super_name.clone()),
             instance_ty: ty::raw(&fullname.0),
             ivars: HashMap::new(), // will be set when processing `#initialize`
             method_sigs: instance_methods,
             const_is_obj: false,
+            interfaces: vec![],
+            is_interface: false,
+            is_enum: false,
         });
 
         // Crete metaclass (which is a subclass of `Class`)
@@ -199,13 +518,39 @@ impl ClassDict {
         let meta_ivars = the_class.ivars.clone();
         self.add_class(SkClass {
             fullname: fullname.meta_name(),
-            typarams,
+            typarams: typarams.to_vec(),
             superclass_fullname: Some(class_fullname("Class")),
             instance_ty: ty::meta(&fullname.0),
             ivars: meta_ivars,
             method_sigs: class_methods,
             const_is_obj: false,
+            interfaces: vec![],
+            is_interface: false,
+            is_enum: false,
         });
         Ok(())
     }
 }
+
+/// Methods of `Object` which cannot be redefined in any class
+const FINAL_METHODS: [&str; 3] = ["class", "equal", "is_a"];
+/// Methods of metaclasses which are defined by the compiler (hir::class_objects)
+const FINAL_CLASS_METHODS: [&str; 1] = ["superclass"];
+
+/// Check the method is not one of `FINAL_METHODS`
+fn check_not_final(clsname: &ClassFullname, sig: &ast::AstMethodSignature) -> Result<(), Error> {
+    let name = sig.name.0.as_str();
+    let is_final = if clsname.is_meta() {
+        FINAL_METHODS.contains(&name) || FINAL_CLASS_METHODS.contains(&name)
+    } else {
+        // `is_a` is defined in builtin/object.sk
+        clsname.0 != "Object" && FINAL_METHODS.contains(&name)
+    };
+    if is_final {
+        return Err(error::program_error(&format!(
+            "method `{}' cannot be overridden",
+            sig.name
+        )));
+    }
+    Ok(())
+}
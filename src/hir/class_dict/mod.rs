@@ -27,7 +27,7 @@ pub fn create(
         .iter()
         .filter_map(|item| match item {
             ast::TopLevelItem::Def(x) => Some(x),
-            ast::TopLevelItem::Expr(_) => None,
+            ast::TopLevelItem::Expr(_) | ast::TopLevelItem::Require(_) => None,
         })
         .collect::<Vec<_>>();
     dict.index_program(&defs)?;
@@ -38,7 +38,7 @@ impl ClassDict {
     /// Return parameters of `initialize`
     fn initializer_params(
         &self,
-        typarams: &[String],
+        typarams: &[TyParam],
         super_class: &TermTy,
         defs: &[ast::Definition],
     ) -> Vec<MethodParam> {
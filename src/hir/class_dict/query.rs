@@ -19,28 +19,51 @@ impl ClassDict {
 
     /// Similar to find_method, but lookup into superclass if not in the class.
     /// Returns Err if not found.
+    /// If `method_tyargs` is empty, method type parameters are left as is
+    /// (to be inferred by the caller)
     pub fn lookup_method(
         &self,
         class: &TermTy,
         method_name: &MethodFirstname,
         method_tyargs: &[TermTy],
     ) -> Result<(MethodSignature, ClassFullname), Error> {
+        let opt_method_tyargs = if method_tyargs.is_empty() {
+            None
+        } else {
+            Some(method_tyargs)
+        };
         match &class.body {
             TyBody::TyRaw | TyBody::TyMeta { .. } | TyBody::TyClass => {
                 let (base_sig, found_cls) = self.lookup_method_(class, class, method_name)?;
-                Ok((base_sig.specialize(None, Some(method_tyargs)), found_cls))
+                self.check_tyargs(
+                    &base_sig.typarams,
+                    method_tyargs,
+                    &TyParamKind::Method,
+                    None,
+                )?;
+                Ok((base_sig.specialize(None, opt_method_tyargs), found_cls))
             }
             TyBody::TySpe { type_args, .. } | TyBody::TySpeMeta { type_args, .. } => {
-                let base_cls = &self
+                let base_cls = self
                     .find_class(&class.base_class_name())
-                    .expect("[BUG] base_cls not found")
-                    .instance_ty;
-                let (base_sig, found_cls) = self.lookup_method_(base_cls, base_cls, method_name)?;
-                Ok((base_sig.specialize(Some(&type_args), Some(method_tyargs)), found_cls))
+                    .expect("[BUG] base_cls not found");
+                self.check_tyargs(&base_cls.typarams, type_args, &TyParamKind::Class, None)?;
+                let base_ty = &base_cls.instance_ty;
+                let (base_sig, found_cls) = self.lookup_method_(base_ty, base_ty, method_name)?;
+                self.check_tyargs(
+                    &base_sig.typarams,
+                    method_tyargs,
+                    &TyParamKind::Method,
+                    Some(&type_args),
+                )?;
+                Ok((
+                    base_sig.specialize(Some(&type_args), opt_method_tyargs),
+                    found_cls,
+                ))
             }
-            TyBody::TyParamRef { .. } => {
-                let o = ty::raw("Object");
-                self.lookup_method_(&o, &o, method_name)
+            // Methods of the upper bound are available (eg. `T : Comparable<T>`)
+            TyBody::TyParamRef { upper_bound, .. } => {
+                self.lookup_method(upper_bound, method_name, method_tyargs)
             }
             _ => todo!("{}", class),
         }
@@ -75,6 +98,35 @@ impl ClassDict {
         }
     }
 
+    /// Return Err if a type argument does not conform to the upper bound
+    /// of the corresponding type parameter.
+    /// The type parameters in the bounds are substituted with `tyargs`
+    /// (eg. `Int` must conform to `Comparable<Int>` for `T : Comparable<T>`)
+    pub fn check_tyargs(
+        &self,
+        typarams: &[TyParam],
+        tyargs: &[TermTy],
+        kind: &TyParamKind,
+        class_tyargs: Option<&[TermTy]>,
+    ) -> Result<(), Error> {
+        if tyargs.len() != typarams.len() {
+            return Ok(());
+        }
+        for (param, arg) in typarams.iter().zip(tyargs) {
+            let bound = match kind {
+                TyParamKind::Class => param.upper_bound.substitute(Some(tyargs), None),
+                TyParamKind::Method => param.upper_bound.substitute(class_tyargs, Some(tyargs)),
+            };
+            if !arg.is_error_type() && !arg.conforms_to(&bound, self) {
+                return Err(error::type_error(&format!(
+                    "type argument {} does not conform to {} (the upper bound of {})",
+                    arg, bound, param.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Find a class
     pub fn find_class(&self, class_fullname: &ClassFullname) -> Option<&SkClass> {
         self.sk_classes.get(class_fullname)
@@ -110,6 +162,78 @@ impl ClassDict {
             .contains_key(&ClassFullname(class_fullname.to_string()))
     }
 
+    /// Return true if there is an interface of the name
+    pub fn is_interface(&self, name: &ClassFullname) -> bool {
+        self.find_class(name).map_or(false, |cls| cls.is_interface)
+    }
+
+    /// Return true if there is an enum of the name
+    pub fn is_enum(&self, name: &ClassFullname) -> bool {
+        self.find_class(name).map_or(false, |cls| cls.is_enum)
+    }
+
+    /// Return true if `case` is a case of the enum `enum_name`
+    /// (whose type parameters are the same as the enum)
+    pub fn is_enum_case_of(&self, case: &ClassFullname, enum_name: &ClassFullname) -> bool {
+        self.is_enum(enum_name)
+            && self.find_class(case).map_or(false, |cls| {
+                cls.superclass_fullname.as_ref() == Some(enum_name)
+            })
+    }
+
+    /// Return true if `ty` is a class (or its subclass) which declares to
+    /// conform to the interface `iface`
+    /// eg. `Int` implements `Comparable<Int>` but not `Comparable<String>`
+    pub fn implements(&self, ty: &TermTy, iface: &TermTy) -> bool {
+        if !self.is_interface(&iface.vtable_name()) {
+            return false;
+        }
+        self.ancestor_types(ty).iter().any(|t| {
+            let (name, tyargs) = match &t.body {
+                TyBody::TyRaw => (t.fullname.clone(), None),
+                TyBody::TySpe { type_args, .. } => (t.base_class_name(), Some(&type_args[..])),
+                _ => return false,
+            };
+            self.find_class(&name).map_or(false, |cls| {
+                cls.interfaces
+                    .iter()
+                    .any(|i| i.substitute(tyargs, None).equals_to(iface))
+            })
+        })
+    }
+
+    /// Return `ty` as the type of the class or the interface `name`, with
+    /// the type arguments mapped through the superclasses and the interfaces
+    /// declared by them. Returns None if `ty` does not conform to `name`
+    /// eg. `Expr<Int>` for `Expr::Value<Int>` and `Expr`,
+    ///     `Comparable<Int>` for `Int` and `Comparable`
+    pub fn as_ancestor(&self, ty: &TermTy, name: &ClassFullname) -> Option<TermTy> {
+        match &ty.body {
+            TyBody::TyRaw | TyBody::TySpe { .. } => (),
+            TyBody::TyParamRef { upper_bound, .. } => return self.as_ancestor(upper_bound, name),
+            _ => return None,
+        }
+        for t in self.ancestor_types(ty) {
+            let (cls_name, tyargs) = match &t.body {
+                TyBody::TySpe { type_args, .. } => (t.base_class_name(), Some(type_args.clone())),
+                _ => (t.fullname.clone(), None),
+            };
+            if &cls_name == name {
+                return Some(t);
+            }
+            let iface = self.find_class(&cls_name).and_then(|cls| {
+                cls.interfaces
+                    .iter()
+                    .find(|iface| &iface.vtable_name() == name)
+                    .map(|iface| iface.substitute(tyargs.as_deref(), None))
+            });
+            if iface.is_some() {
+                return iface;
+            }
+        }
+        None
+    }
+
     /// Find the superclass
     /// Return None if the class is `Object`
     pub fn get_superclass(&self, classname: &ClassFullname) -> Option<&SkClass> {
@@ -148,6 +272,27 @@ impl ClassDict {
         false
     }
 
+    /// Return the direct subclasses of a class, sorted by name
+    pub fn subclasses_of(&self, classname: &ClassFullname) -> Vec<ClassFullname> {
+        let mut v = self
+            .sk_classes
+            .values()
+            .filter(|cls| cls.superclass_fullname.as_ref() == Some(classname))
+            .map(|cls| cls.fullname.clone())
+            .collect::<Vec<_>>();
+        v.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        v
+    }
+
+    /// Return the class and its descendant classes
+    pub fn descendant_classes(&self, classname: &ClassFullname) -> Vec<ClassFullname> {
+        let mut v = vec![classname.clone()];
+        for sub in self.subclasses_of(classname) {
+            v.append(&mut self.descendant_classes(&sub));
+        }
+        v
+    }
+
     pub fn find_ivar(&self, classname: &ClassFullname, ivar_name: &str) -> Option<&SkIVar> {
         let class = self.sk_classes.get(&classname).unwrap_or_else(|| {
             panic!(
@@ -0,0 +1,88 @@
+//! Creates the class objects of all the classes.
+//!
+//! Each object holds a reference to its class object (see code_gen/utils.rs),
+//! so every class needs a constant for its class object even if the class is
+//! not referred from the program.
+use crate::error::Error;
+use crate::hir::hir_maker::HirMaker;
+use crate::hir::*;
+
+impl HirMaker {
+    /// Register the constants of the class objects and define `superclass`
+    /// of the metaclasses
+    pub(super) fn define_class_objects(&mut self) {
+        let mut classes = self
+            .class_dict
+            .sk_classes
+            .values()
+            // Interfaces do not have class objects
+            .filter(|cls| !cls.fullname.is_meta() && !cls.is_interface)
+            .cloned()
+            .collect::<Vec<_>>();
+        // Sort to make the output stable
+        classes.sort_unstable_by(|a, b| a.fullname.0.cmp(&b.fullname.0));
+
+        for cls in classes {
+            let const_name = cls.class_object_const();
+            if !self.constants.contains_key(&const_name) {
+                let name = const_name_of(&cls.fullname);
+                let idx = self.register_string_literal(&name.string());
+                let expr = Hir::class_literal(cls.class_ty(), &name, idx);
+                self.register_const_full(const_name, expr);
+            }
+            // The class of `Class` is `Class` itself. Its `superclass` is
+            // defined in builtin/class.sk
+            if cls.fullname.0 != "Class" {
+                match self.create_superclass_method(&cls) {
+                    Ok(method) => {
+                        let meta_name = cls.fullname.meta_name();
+                        self.class_dict
+                            .add_method(&meta_name, method.signature.clone());
+                        self.method_dict.add_method(&meta_name, method);
+                    }
+                    Err(e) => self.errors.push(e),
+                }
+            }
+        }
+    }
+
+    /// Create `Meta:A#superclass` which returns `Some(B)` for `class A : B`
+    /// (or `None` for `Object`)
+    fn create_superclass_method(&mut self, cls: &SkClass) -> Result<SkMethod, Error> {
+        let ret_ty = ty::spe("Option", vec![ty::class()]);
+        let ret = match &cls.superclass_fullname {
+            Some(super_name) => {
+                // eg. `Expr` for `Expr::Value<T> : Expr<T>`
+                let base_name = super_name.0.split('<').next().unwrap();
+                let super_obj =
+                    self.convert_const_ref(&const_name_of(&class_fullname(base_name)))?;
+                let some = self.convert_const_ref(&ConstName {
+                    names: vec!["Option".to_string(), "Some".to_string()],
+                    args: vec![const_name(vec!["Class".to_string()])],
+                })?;
+                self._make_method_call(some, &method_firstname("new"), vec![super_obj], &[])?
+            }
+            None => {
+                self.convert_const_ref(&const_name(vec!["Option".to_string(), "None".to_string()]))?
+            }
+        };
+        let sig = MethodSignature {
+            fullname: method_fullname(&cls.fullname.meta_name(), "superclass"),
+            ret_ty: ret_ty.clone(),
+            params: vec![],
+            typarams: vec![],
+        };
+        Ok(SkMethod {
+            signature: sig,
+            body: SkMethodBody::ShiikaMethodBody {
+                exprs: HirExpressions::new(vec![Hir::bit_cast(ret_ty, ret)]),
+            },
+            lvars: vec![],
+        })
+    }
+}
+
+/// Make `ConstName` from a class name (eg. `["A", "B"]` for `A::B`)
+fn const_name_of(name: &ClassFullname) -> ConstName {
+    const_name(name.0.split("::").map(|s| s.to_string()).collect())
+}
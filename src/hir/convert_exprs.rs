@@ -61,17 +61,47 @@ impl HirMaker {
     ) -> Result<HirExpressions, Error> {
         let hir_exprs = exprs
             .iter()
-            .map(|expr| self.convert_expr(expr))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|expr| self.convert_expr_or_error(expr))
+            .collect::<Vec<_>>();
 
         Ok(HirExpressions::new(hir_exprs))
     }
 
+    /// Convert `expr` into HIR. If it has an error, record it and
+    /// return an error expression so that the compilation can go on
+    pub(super) fn convert_expr_or_error(&mut self, expr: &AstExpression) -> HirExpression {
+        match self.convert_expr(expr) {
+            Ok(hir_expr) => hir_expr,
+            Err(e) => {
+                self.errors.push(e);
+                let mut hir_expr = Hir::error_expression();
+                hir_expr.span = expr.span.clone();
+                hir_expr
+            }
+        }
+    }
+
+    /// Convert `expr` into HIR and set its source location
     pub(super) fn convert_expr(&mut self, expr: &AstExpression) -> Result<HirExpression, Error> {
+        let mut hir_expr = self
+            .convert_expr_(expr)
+            .map_err(|e| e.located_at(&expr.span))?;
+        if hir_expr.span.is_none() {
+            hir_expr.span = expr.span.clone();
+        }
+        Ok(hir_expr)
+    }
+
+    fn convert_expr_(&mut self, expr: &AstExpression) -> Result<HirExpression, Error> {
         match &expr.body {
             AstExpressionBody::LogicalNot { expr } => self.convert_logical_not(expr),
             AstExpressionBody::LogicalAnd { left, right } => self.convert_logical_and(left, right),
             AstExpressionBody::LogicalOr { left, right } => self.convert_logical_or(left, right),
+            AstExpressionBody::Range {
+                first,
+                last,
+                exclude_end,
+            } => self.convert_range_expr(first, last, exclude_end),
             AstExpressionBody::If {
                 cond_expr,
                 then_exprs,
@@ -83,10 +113,38 @@ impl HirMaker {
                 body_exprs,
             } => self.convert_while_expr(cond_expr, body_exprs),
 
+            AstExpressionBody::For {
+                var_name,
+                iterable,
+                body_exprs,
+            } => self.convert_for_expr(var_name, iterable, body_exprs),
+
+            AstExpressionBody::Match {
+                cond_expr,
+                clauses,
+                else_exprs,
+            } => self.convert_match_expr(cond_expr, clauses, else_exprs),
+
+            AstExpressionBody::Case {
+                cond_expr,
+                clauses,
+                else_exprs,
+            } => self.convert_case_expr(cond_expr, clauses, else_exprs),
+
+            AstExpressionBody::Begin {
+                body_exprs,
+                rescue_clauses,
+                ensure_exprs,
+            } => self.convert_begin_expr(body_exprs, rescue_clauses, ensure_exprs),
+
             AstExpressionBody::Break => self.convert_break_expr(),
 
+            AstExpressionBody::Next { arg } => self.convert_next_expr(arg),
+
             AstExpressionBody::Return { arg } => self.convert_return_expr(arg),
 
+            AstExpressionBody::Raise { expr } => self.convert_raise_expr(expr),
+
             AstExpressionBody::LVarAssign { name, rhs, is_var } => {
                 self.convert_lvar_assign(name, &*rhs, is_var)
             }
@@ -99,6 +157,10 @@ impl HirMaker {
                 self.convert_const_assign(names, &*rhs)
             }
 
+            AstExpressionBody::MultipleAssignment { names, rhs } => {
+                self.convert_multiple_assignment(names, rhs)
+            }
+
             AstExpressionBody::MethodCall {
                 receiver_expr,
                 method_name,
@@ -107,11 +169,20 @@ impl HirMaker {
                 ..
             } => self.convert_method_call(receiver_expr, method_name, arg_exprs, type_args),
 
+            AstExpressionBody::KeywordArg { name, .. } => Err(error::syntax_error(&format!(
+                "keyword arg `{}' is only allowed in method calls",
+                name
+            ))),
+
+            AstExpressionBody::SplatArg { .. } => Err(error::syntax_error(
+                "`*' arg is only allowed in method calls",
+            )),
+
             AstExpressionBody::LambdaExpr {
                 params,
                 exprs,
                 is_fn,
-            } => self.convert_lambda_expr(params, exprs, is_fn),
+            } => self.convert_lambda_expr(params, exprs, *is_fn, &[], None),
 
             AstExpressionBody::BareName(name) => self.convert_bare_name(name),
 
@@ -123,6 +194,10 @@ impl HirMaker {
 
             AstExpressionBody::ArrayLiteral(exprs) => self.convert_array_literal(exprs),
 
+            AstExpressionBody::TupleLiteral(exprs) => self.convert_tuple_literal(exprs),
+
+            AstExpressionBody::HashLiteral(pairs) => self.convert_hash_literal(pairs),
+
             AstExpressionBody::FloatLiteral { value } => Ok(Hir::float_literal(*value)),
 
             AstExpressionBody::DecimalLiteral { value } => Ok(Hir::decimal_literal(*value)),
@@ -162,6 +237,30 @@ impl HirMaker {
         Ok(Hir::logical_or(left_hir, right_hir))
     }
 
+    /// Generate HIR for a range literal
+    /// `a..b` is expanded into `Range.literal<T>(a, b, false)`
+    fn convert_range_expr(
+        &mut self,
+        first: &AstExpression,
+        last: &AstExpression,
+        exclude_end: &bool,
+    ) -> Result<HirExpression, Error> {
+        let first_hir = self.convert_expr(first)?;
+        let last_hir = self.convert_expr(last)?;
+        let item_ty = if first_hir.ty == last_hir.ty {
+            first_hir.ty.clone()
+        } else {
+            self.nearest_common_ancestor_type(&first_hir.ty, &last_hir.ty)
+        };
+        let cls = self.convert_const_ref(&const_name(vec!["Range".to_string()]))?;
+        self._make_method_call(
+            cls,
+            &method_firstname("literal"),
+            vec![first_hir, last_hir, Hir::boolean_literal(*exclude_end)],
+            &[item_ty],
+        )
+    }
+
     fn convert_if_expr(
         &mut self,
         cond_expr: &AstExpression,
@@ -171,7 +270,18 @@ impl HirMaker {
         let cond_hir = self.convert_expr(cond_expr)?;
         type_checking::check_condition_ty(&cond_hir.ty, "if")?;
 
-        let mut then_hirs = self.convert_exprs(then_exprs)?;
+        let mut then_hirs = match self._narrowing_by_is_a(cond_expr, &cond_hir) {
+            Some((name, narrowed_ty)) => {
+                let orig = self.ctx.narrowed_lvars.insert(name.clone(), narrowed_ty);
+                let result = self.convert_exprs(then_exprs);
+                match orig {
+                    Some(t) => self.ctx.narrowed_lvars.insert(name, t),
+                    None => self.ctx.narrowed_lvars.remove(&name),
+                };
+                result?
+            }
+            None => self.convert_exprs(then_exprs)?,
+        };
         let mut else_hirs = match else_exprs {
             Some(exprs) => self.convert_exprs(exprs)?,
             None => HirExpressions::new(vec![]),
@@ -196,6 +306,63 @@ impl HirMaker {
         Ok(Hir::if_expression(if_ty, cond_hir, then_hirs, else_hirs))
     }
 
+    /// If `cond_expr` is like `x.is_a(Foo)`, return the name of the variable
+    /// and the type it should have in the then-clause.
+    /// Only readonly variables are narrowed because `var`s may be reassigned
+    /// to another type of value in the clause.
+    fn _narrowing_by_is_a(
+        &self,
+        cond_expr: &AstExpression,
+        cond_hir: &HirExpression,
+    ) -> Option<(String, TermTy)> {
+        let name = match &cond_expr.body {
+            AstExpressionBody::MethodCall {
+                receiver_expr: Some(recv),
+                method_name,
+                arg_exprs,
+                ..
+            } if method_name.0 == "is_a" && arg_exprs.len() == 1 => match &recv.body {
+                AstExpressionBody::BareName(name) => name,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let (recv_ty, cls_ty) = match &cond_hir.node {
+            HirExpressionBase::HirMethodCall {
+                receiver_expr,
+                arg_exprs,
+                ..
+            } => (&receiver_expr.ty, &arg_exprs[0].ty),
+            _ => return None,
+        };
+        // Generic classes are not supported because the type arguments are unknown
+        let narrowed_ty = match &cls_ty.body {
+            TyBody::TyMeta { base_fullname } => ty::raw(base_fullname),
+            _ => return None,
+        };
+        if !self._is_readonly_var(name)
+            || narrowed_ty.equals_to(recv_ty)
+            || !narrowed_ty.conforms_to(recv_ty, &self.class_dict)
+        {
+            return None;
+        }
+        Some((name.to_string(), narrowed_ty))
+    }
+
+    /// Return true if `name` is a local variable declared without `var`, or
+    /// an argument
+    fn _is_readonly_var(&self, name: &str) -> bool {
+        for (lvars, params, _) in self.ctx.lvar_scopes() {
+            if let Some(lvar) = lvars.get(name) {
+                return lvar.readonly;
+            }
+            if signature::find_param(&params, name).is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
     fn convert_while_expr(
         &mut self,
         cond_expr: &AstExpression,
@@ -206,10 +373,466 @@ impl HirMaker {
 
         let mut current = CtxKind::While;
         self.ctx.swap_current(&mut current);
+        let outer_has_break = std::mem::replace(&mut self.ctx.while_has_break, false);
         let body_hirs = self.convert_exprs(body_exprs)?;
+        let has_break = std::mem::replace(&mut self.ctx.while_has_break, outer_has_break);
         self.ctx.swap_current(&mut current);
 
-        Ok(Hir::while_expression(cond_hir, body_hirs))
+        // `while true` (and `loop`) without `break` never ends
+        let is_infinite = match &cond_expr.body {
+            AstExpressionBody::PseudoVariable(Token::KwTrue) => true,
+            _ => false,
+        };
+        let while_ty = if is_infinite && !has_break {
+            ty::raw("Never")
+        } else {
+            ty::raw("Void")
+        };
+        Ok(Hir::while_expression(while_ty, cond_hir, body_hirs))
+    }
+
+    /// Generate HIR for a `for` expression
+    /// `for x in ary ... end` is expanded into `ary.each{|x: T| ... }`
+    /// unless `ary` is an Array
+    fn convert_for_expr(
+        &mut self,
+        var_name: &str,
+        iterable: &AstExpression,
+        body_exprs: &[AstExpression],
+    ) -> Result<HirExpression, Error> {
+        let iterable_hir = self.convert_expr(iterable)?;
+        if iterable_hir.ty.is_error_type() {
+            return Ok(Hir::error_expression());
+        }
+        if let TyBody::TySpe {
+            base_name,
+            type_args,
+        } = &iterable_hir.ty.body
+        {
+            if base_name == "Array" {
+                let item_ty = type_args[0].clone();
+                return self._convert_for_array(var_name, iterable_hir, item_ty, body_exprs);
+            }
+        }
+        let each = method_firstname("each");
+        let (sig, _) = self
+            .class_dict
+            .lookup_method(&iterable_hir.ty, &each, &[])?;
+        // eg. `Int` for `Range<Int>#each(f: Fn1<Int, Void>)`
+        let item_ty = match sig.params.as_slice() {
+            [param] if param.ty.fn_x_info().is_some() => match &param.ty.body {
+                TyBody::TySpe { type_args, .. } if type_args.len() == 2 => {
+                    Some(type_args[0].clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let item_ty = match item_ty {
+            Some(t) => t,
+            None => {
+                return Err(error::type_error(&format!(
+                    "cannot use {} in `for' because {} does not take a block with one param",
+                    iterable_hir.ty, sig.fullname
+                )))
+            }
+        };
+        let params = vec![MethodParam {
+            name: var_name.to_string(),
+            ty: item_ty,
+            default_expr: None,
+            is_rest: false,
+        }];
+        let mut lambda_ctx = LambdaCtx::new(false, params);
+        lambda_ctx.in_for = true;
+        let block = self.convert_lambda_expr_(lambda_ctx, body_exprs, None)?;
+        self._make_method_call(iterable_hir, &each, vec![block], &[])
+    }
+
+    /// Expand `for x in ary ... end` into a `while` with an index
+    /// (so that `return` in the body returns from the method)
+    ///
+    ///     ary@0 = ary
+    ///     var i@1 = 0
+    ///     while i@1 < ary@0.length
+    ///       x = ary@0[i@1]
+    ///       i@1 += 1
+    ///       ...
+    ///     end
+    fn _convert_for_array(
+        &mut self,
+        var_name: &str,
+        ary_hir: HirExpression,
+        item_ty: TermTy,
+        body_exprs: &[AstExpression],
+    ) -> Result<HirExpression, Error> {
+        let ary_ty = ary_hir.ty.clone();
+        let ary_name = self.generate_tmp_name("for");
+        self.ctx.declare_lvar(&ary_name, ary_ty.clone(), true);
+        let idx_name = self.generate_tmp_name("for");
+        self.ctx.declare_lvar(&idx_name, ty::raw("Int"), false);
+        let ary_ref = || Hir::lvar_ref(ary_ty.clone(), ary_name.clone());
+        let idx_ref = || Hir::lvar_ref(ty::raw("Int"), idx_name.clone());
+
+        let length = self._make_method_call(ary_ref(), &method_firstname("length"), vec![], &[])?;
+        let cond_hir =
+            self._make_method_call(idx_ref(), &method_firstname("<"), vec![length], &[])?;
+        let item =
+            self._make_method_call(ary_ref(), &method_firstname("[]"), vec![idx_ref()], &[])?;
+        let succ = self._make_method_call(
+            idx_ref(),
+            &method_firstname("+"),
+            vec![Hir::decimal_literal(1)],
+            &[],
+        )?;
+
+        let mut current = CtxKind::While;
+        self.ctx.swap_current(&mut current);
+        let outer_has_break = std::mem::replace(&mut self.ctx.while_has_break, false);
+        let var_storage = self.generate_tmp_name(var_name);
+        let shadowed = self.ctx.push_loop_var(var_name, &var_storage, item_ty);
+        let narrowed = self.ctx.narrowed_lvars.remove(var_name);
+        let body_hirs = self.convert_exprs(body_exprs);
+        if let Some(ty) = narrowed {
+            self.ctx.narrowed_lvars.insert(var_name.to_string(), ty);
+        }
+        self.ctx.pop_loop_var(var_name, shadowed);
+        self.ctx.while_has_break = outer_has_break;
+        self.ctx.swap_current(&mut current);
+        let mut body_hirs = body_hirs?;
+        body_hirs.exprs.splice(
+            0..0,
+            vec![
+                Hir::lvar_assign(&var_storage, item),
+                Hir::lvar_assign(&idx_name, succ),
+            ],
+        );
+
+        Ok(Hir::sequence(HirExpressions::new(vec![
+            Hir::lvar_assign(&ary_name, ary_hir),
+            Hir::lvar_assign(&idx_name, Hir::decimal_literal(0)),
+            Hir::while_expression(ty::raw("Void"), cond_hir, body_hirs),
+        ])))
+    }
+
+    fn convert_match_expr(
+        &mut self,
+        cond_expr: &AstExpression,
+        clauses: &[AstMatchClause],
+        else_exprs: &Option<Vec<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let cond_hir = self.convert_expr(cond_expr)?;
+        let cond_ty = cond_hir.ty.clone();
+        if cond_ty.is_error_type() {
+            return Ok(Hir::error_expression());
+        }
+        let base_class = match &cond_ty.body {
+            TyBody::TyRaw => cond_ty.fullname.clone(),
+            TyBody::TySpe { .. } => cond_ty.base_class_name(),
+            _ => {
+                return Err(error::type_error(&format!(
+                    "cannot match on a value of {}",
+                    cond_ty
+                )))
+            }
+        };
+        // Store the value to a temporary lvar
+        let tmp_name = self.generate_tmp_name("match");
+        self.ctx.declare_lvar(&tmp_name, cond_ty.clone(), true);
+        let cond_assign_hir = Hir::lvar_assign(&tmp_name, cond_hir);
+
+        let mut covered = vec![];
+        let mut hir_clauses = vec![];
+        for clause in clauses {
+            let class = self._resolve_match_class(&clause.class_name, &base_class)?;
+            let body_exprs = self._convert_match_clause(&tmp_name, &cond_ty, &class, clause)?;
+            hir_clauses.push(HirMatchClause {
+                classes: self.class_dict.descendant_classes(&class),
+                body_exprs,
+            });
+            covered.push(class);
+        }
+
+        let else_hirs = match else_exprs {
+            Some(exprs) => self.convert_exprs(exprs)?,
+            None => {
+                type_checking::check_match_exhaustiveness(&self.class_dict, &base_class, &covered)?;
+                // Not reached because the clauses cover all the cases
+                let obj = Hir::bit_cast(ty::raw("Object"), Hir::lvar_ref(cond_ty, tmp_name));
+                let idx = self.register_string_literal("[match: no clause matched]");
+                let panic = self._make_method_call(
+                    obj,
+                    &method_firstname("panic"),
+                    vec![Hir::string_literal(idx)],
+                    &[],
+                )?;
+                HirExpressions::new(vec![panic])
+            }
+        };
+        hir_clauses.push(HirMatchClause {
+            classes: vec![],
+            body_exprs: else_hirs,
+        });
+
+        let match_ty = self._unify_clauses_ty(
+            hir_clauses
+                .iter_mut()
+                .map(|clause| &mut clause.body_exprs)
+                .collect(),
+        );
+        Ok(Hir::match_expression(match_ty, cond_assign_hir, hir_clauses))
+    }
+
+    /// Generate HIR for a `case` expression
+    /// `case x when 1, 2 then a else b end` is expanded into
+    /// `if (tmp = x) == 1 || tmp == 2 then a else b end`
+    fn convert_case_expr(
+        &mut self,
+        cond_expr: &AstExpression,
+        clauses: &[AstCaseClause],
+        else_exprs: &Option<Vec<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let cond_hir = self.convert_expr(cond_expr)?;
+        let cond_ty = cond_hir.ty.clone();
+        if cond_ty.is_error_type() {
+            return Ok(Hir::error_expression());
+        }
+        // Store the value to a temporary lvar
+        let tmp_name = self.generate_tmp_name("case");
+        self.ctx.declare_lvar(&tmp_name, cond_ty.clone(), true);
+        let mut cond_assign_hir = Some(Hir::lvar_assign(&tmp_name, cond_hir));
+
+        let mut tests = vec![];
+        let mut bodies = vec![];
+        for clause in clauses {
+            let mut test: Option<HirExpression> = None;
+            for value in &clause.values {
+                // The first comparison also assigns the value to the lvar
+                let lhs = match cond_assign_hir.take() {
+                    Some(assign) => assign,
+                    None => Hir::lvar_ref(cond_ty.clone(), tmp_name.clone()),
+                };
+                let value_hir = self.convert_expr(value)?;
+                let eq =
+                    self._make_method_call(lhs, &method_firstname("=="), vec![value_hir], &[])?;
+                type_checking::check_condition_ty(&eq.ty, "when")?;
+                test = Some(match test {
+                    Some(t) => Hir::logical_or(t, eq),
+                    None => eq,
+                });
+            }
+            tests.push(test.expect("[BUG] when clause without values"));
+            bodies.push(self.convert_exprs(&clause.body_exprs)?);
+        }
+        bodies.push(match else_exprs {
+            Some(exprs) => self.convert_exprs(exprs)?,
+            None => HirExpressions::new(vec![]),
+        });
+
+        let case_ty = self._unify_clauses_ty(bodies.iter_mut().collect());
+        // Build the `if` from the last clause
+        let mut else_hirs = bodies.pop().unwrap();
+        while let Some(then_hirs) = bodies.pop() {
+            let test = tests.pop().unwrap();
+            let if_hir = Hir::if_expression(case_ty.clone(), test, then_hirs, else_hirs);
+            else_hirs = HirExpressions::new(vec![if_hir]);
+        }
+        Ok(else_hirs.exprs.pop().unwrap())
+    }
+
+    /// Resolve the class name in a `when` clause
+    /// eg. `Leaf` resolves to `Tree::Leaf` when matching on a `Tree`
+    fn _resolve_match_class(
+        &self,
+        name: &ConstName,
+        base_class: &ClassFullname,
+    ) -> Result<ClassFullname, Error> {
+        let s = name.names.join("::");
+        let candidates = vec![
+            format!("{}::{}", base_class.0, s),
+            s.clone(),
+            format!("{}::{}", self.ctx.const_namespace(), s),
+        ];
+        let class = match candidates.into_iter().find(|c| self.class_dict.class_exists(c)) {
+            Some(c) => class_fullname(c),
+            None => {
+                return Err(error::name_error(&format!(
+                    "unknown class `{}' in `when'",
+                    s
+                )))
+            }
+        };
+        if !self.class_dict.descendant_classes(base_class).contains(&class) {
+            return Err(error::type_error(&format!(
+                "{} never matches because it is not a subclass of {}",
+                class, base_class
+            )));
+        }
+        Ok(class)
+    }
+
+    /// Convert a `when` clause into the assignments of the bindings followed by the body
+    fn _convert_match_clause(
+        &mut self,
+        tmp_name: &str,
+        cond_ty: &TermTy,
+        class: &ClassFullname,
+        clause: &AstMatchClause,
+    ) -> Result<HirExpressions, Error> {
+        let narrowed_ty = match &cond_ty.body {
+            TyBody::TySpe { type_args, .. }
+                if self.class_dict.get_class(class, "_convert_match_clause").typarams.len()
+                    == type_args.len() =>
+            {
+                ty::spe(&class.0, type_args.clone())
+            }
+            _ => class.instance_ty(),
+        };
+        let mut ivars = self
+            .class_dict
+            .get_class(class, "_convert_match_clause")
+            .ivars
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        ivars.sort_unstable_by_key(|ivar| ivar.idx);
+        if !clause.bindings.is_empty() && clause.bindings.len() != ivars.len() {
+            return Err(error::program_error(&format!(
+                "{} has {} instance variables but {} names are given",
+                class,
+                ivars.len(),
+                clause.bindings.len()
+            )));
+        }
+
+        let mut exprs = vec![];
+        for (name, ivar) in clause.bindings.iter().zip(ivars.iter()) {
+            if name == "_" {
+                continue;
+            }
+            let obj = Hir::bit_cast(
+                narrowed_ty.clone(),
+                Hir::lvar_ref(cond_ty.clone(), tmp_name.to_string()),
+            );
+            let value =
+                self._make_method_call(obj, &method_firstname(&ivar.accessor_name()), vec![], &[])?;
+            exprs.push(self._bind_lvar(name, value)?);
+        }
+        exprs.append(&mut self.convert_exprs(&clause.body_exprs)?.exprs);
+        Ok(HirExpressions::new(exprs))
+    }
+
+    /// Decide the type of a `match` (or `begin`) and voidify/bitcast the clauses to it
+    fn _unify_clauses_ty(&self, clauses: Vec<&mut HirExpressions>) -> TermTy {
+        let tys = clauses
+            .iter()
+            .map(|exprs| exprs.ty.clone())
+            .filter(|t| !t.is_never_type())
+            .collect::<Vec<_>>();
+        if tys.is_empty() {
+            return ty::raw("Never");
+        }
+        let match_ty = if tys.iter().any(|t| t.is_void_type()) {
+            ty::raw("Void")
+        } else {
+            let mut t = tys[0].clone();
+            for t2 in &tys[1..] {
+                t = self.nearest_common_ancestor_type(&t, t2);
+            }
+            t
+        };
+        for exprs in clauses {
+            let t = &exprs.ty;
+            if t.is_never_type() || t.equals_to(&match_ty) {
+                // ok
+            } else if match_ty.is_void_type() {
+                exprs.voidify();
+            } else {
+                exprs.bitcast_to(match_ty.clone());
+            }
+        }
+        match_ty
+    }
+
+    /// Assign `value` to a local variable. Declare it if not exist
+    fn _bind_lvar(&mut self, name: &str, value: HirExpression) -> Result<HirExpression, Error> {
+        if let Some(lvar_info) = self._find_var(name, true)? {
+            type_checking::check_reassign_var(&lvar_info.ty(), &value.ty, name)?;
+            Ok(lvar_info.assign_expr(value))
+        } else {
+            self.ctx.declare_lvar(name, value.ty.clone(), false);
+            Ok(Hir::lvar_assign(name, value))
+        }
+    }
+
+    fn convert_begin_expr(
+        &mut self,
+        body_exprs: &[AstExpression],
+        rescue_clauses: &[AstRescueClause],
+        ensure_exprs: &Option<Vec<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let mut body_hirs = self.convert_exprs(body_exprs)?;
+        let mut hir_clauses = vec![];
+        for clause in rescue_clauses {
+            hir_clauses.push(self._convert_rescue_clause(clause)?);
+        }
+        let ensure_hirs = match ensure_exprs {
+            Some(exprs) => Some(self.convert_exprs(exprs)?),
+            None => None,
+        };
+
+        let mut clauses = vec![&mut body_hirs];
+        clauses.extend(hir_clauses.iter_mut().map(|clause| &mut clause.body_exprs));
+        let begin_ty = self._unify_clauses_ty(clauses);
+        Ok(Hir::begin_expression(
+            begin_ty,
+            body_hirs,
+            hir_clauses,
+            ensure_hirs,
+        ))
+    }
+
+    /// Convert a `rescue` clause into the binding of the exception followed by the body
+    fn _convert_rescue_clause(
+        &mut self,
+        clause: &AstRescueClause,
+    ) -> Result<HirRescueClause, Error> {
+        // Bare `rescue` catches `StandardError`
+        let classes = if clause.class_names.is_empty() {
+            vec![class_fullname("StandardError")]
+        } else {
+            clause
+                .class_names
+                .iter()
+                .map(|name| self._resolve_rescue_class(name))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut exprs = vec![];
+        if let Some(name) = &clause.var_name {
+            let mut ty = classes[0].instance_ty();
+            for class in &classes[1..] {
+                ty = self.nearest_common_ancestor_type(&ty, &class.instance_ty());
+            }
+            exprs.push(self._bind_lvar(name, Hir::current_exception(ty))?);
+        }
+        exprs.append(&mut self.convert_exprs(&clause.body_exprs)?.exprs);
+        Ok(HirRescueClause {
+            classes: classes
+                .iter()
+                .flat_map(|class| self.class_dict.descendant_classes(class))
+                .collect(),
+            body_exprs: HirExpressions::new(exprs),
+        })
+    }
+
+    /// Resolve the class name in a `rescue` clause
+    fn _resolve_rescue_class(&self, name: &ConstName) -> Result<ClassFullname, Error> {
+        let name = self._resolve_class_name(name);
+        self._check_class_exists(&name)?;
+        let class = name.to_class_fullname();
+        type_checking::check_rescue_class(&self.class_dict, &class)?;
+        Ok(class)
     }
 
     fn convert_break_expr(&mut self) -> Result<HirExpression, Error> {
@@ -225,6 +848,7 @@ impl HirMaker {
                 from = HirBreakFrom::Block;
             }
         } else if self.ctx.current == CtxKind::While {
+            self.ctx.while_has_break = true;
             from = HirBreakFrom::While;
         } else {
             return Err(error::program_error("`break' outside a loop"));
@@ -232,6 +856,39 @@ impl HirMaker {
         Ok(Hir::break_expression(from))
     }
 
+    fn convert_next_expr(
+        &mut self,
+        arg: &Option<Box<AstExpression>>,
+    ) -> Result<HirExpression, Error> {
+        let arg_expr = if let Some(x) = arg {
+            self.convert_expr(x)?
+        } else {
+            void_const_ref()
+        };
+        let from;
+        if self.ctx.current == CtxKind::Lambda {
+            let lambda_ctx = self.ctx.lambda_mut();
+            if lambda_ctx.is_fn {
+                return Err(error::program_error(
+                    "`next' inside a fn (use `return' instead)",
+                ));
+            }
+            // Checked with the type of the block later
+            lambda_ctx.next_arg_tys.push(arg_expr.ty.clone());
+            from = HirNextFrom::Block;
+        } else if self.ctx.current == CtxKind::While {
+            if arg.is_some() {
+                return Err(error::program_error(
+                    "`next' in `while' cannot take a value",
+                ));
+            }
+            from = HirNextFrom::While;
+        } else {
+            return Err(error::program_error("`next' outside a loop"));
+        }
+        Ok(Hir::next_expression(from, arg_expr))
+    }
+
     fn convert_return_expr(&mut self, arg: &Option<Box<AstExpression>>) -> Result<HirExpression, Error> {
         let from = self._validate_return()?;
         let arg_expr = if let Some(x) = arg {
@@ -243,11 +900,27 @@ impl HirMaker {
         Ok(Hir::return_expression(from, arg_expr))
     }
 
+    fn convert_raise_expr(&mut self, expr: &AstExpression) -> Result<HirExpression, Error> {
+        let mut expr_hir = self.convert_expr(expr)?;
+        // `raise "msg"` is a shorthand for `raise RuntimeError.new("msg")`
+        if expr_hir.ty == ty::raw("String") {
+            let cls = self.convert_const_ref(&const_name(vec!["RuntimeError".to_string()]))?;
+            expr_hir =
+                self._make_method_call(cls, &method_firstname("new"), vec![expr_hir], &[])?;
+        }
+        type_checking::check_raise_arg_ty(&self.class_dict, &expr_hir.ty)?;
+        Ok(Hir::raise_expression(expr_hir))
+    }
+
     /// Check if `return' is valid in the current context
     fn _validate_return(&self) -> Result<HirReturnFrom, Error> {
         if let Some(lambda_ctx) = self.ctx.lambdas.last() {
             if lambda_ctx.is_fn {
                 Ok(HirReturnFrom::Fn)
+            } else if lambda_ctx.in_for {
+                Err(error::program_error(
+                    "`return' cannot be used in `for' except for an Array",
+                ))
             } else if self.ctx.method.is_some() {
                 Ok(HirReturnFrom::Block)
             } else {
@@ -276,9 +949,20 @@ impl HirMaker {
         rhs: &AstExpression,
         is_var: &bool,
     ) -> Result<HirExpression, Error> {
-        let expr = self.convert_expr(rhs)?;
+        // Declare the variable even if `rhs` has an error
+        let expr = self.convert_expr_or_error(rhs);
+        self._assign_lvar(name, expr, *is_var)
+    }
+
+    /// Generate HIR to assign `expr` to the lvar `name`
+    fn _assign_lvar(
+        &mut self,
+        name: &str,
+        expr: HirExpression,
+        is_var: bool,
+    ) -> Result<HirExpression, Error> {
         // For `var x`, `x` should not be exist
-        if *is_var && self._lookup_var(name).is_some() {
+        if is_var && self._lookup_var(name).is_some() {
             return Err(error::program_error(&format!(
                 "variable `{}' already exists",
                 name
@@ -295,13 +979,73 @@ impl HirMaker {
         }
     }
 
+    /// Generate HIR for a multiple assignment
+    /// - `a, b = b, a` is expanded into `t1 = b; t2 = a; a = t1; b = t2`
+    /// - `a, b = tuple` is expanded into `t = tuple; a = t.0; b = t.1`
+    fn convert_multiple_assignment(
+        &mut self,
+        names: &[String],
+        rhs: &[AstExpression],
+    ) -> Result<HirExpression, Error> {
+        let mut hir_exprs = vec![];
+        let mut values = vec![];
+        if let [value] = rhs {
+            let value_hir = self.convert_expr(value)?;
+            if value_hir.ty.is_error_type() {
+                return Ok(Hir::error_expression());
+            }
+            if value_hir.ty.tuple_size() != Some(names.len()) {
+                return Err(error::type_error(&format!(
+                    "cannot assign {} to {} variables (expected a tuple of {} elements)",
+                    value_hir.ty,
+                    names.len(),
+                    names.len()
+                )));
+            }
+            let ty = value_hir.ty.clone();
+            let tmp_name = self.generate_tmp_name("masgn");
+            self.ctx.declare_lvar(&tmp_name, ty.clone(), true);
+            hir_exprs.push(Hir::lvar_assign(&tmp_name, value_hir));
+            // eg. `t.0`, `t.1`
+            for i in 0..names.len() {
+                let tmp_ref = Hir::lvar_ref(ty.clone(), tmp_name.clone());
+                let method_name = method_firstname(&i.to_string());
+                values.push(self._make_method_call(tmp_ref, &method_name, vec![], &[])?);
+            }
+        } else {
+            if rhs.len() != names.len() {
+                return Err(error::program_error(&format!(
+                    "tried to assign {} values to {} variables",
+                    rhs.len(),
+                    names.len()
+                )));
+            }
+            // Evaluate all the values before assigning (for `a, b = b, a`)
+            for value in rhs {
+                let value_hir = self.convert_expr(value)?;
+                let ty = value_hir.ty.clone();
+                let tmp_name = self.generate_tmp_name("masgn");
+                self.ctx.declare_lvar(&tmp_name, ty.clone(), true);
+                hir_exprs.push(Hir::lvar_assign(&tmp_name, value_hir));
+                values.push(Hir::lvar_ref(ty, tmp_name));
+            }
+        }
+        for (name, value) in names.iter().zip(values) {
+            hir_exprs.push(self._assign_lvar(name, value, false)?);
+        }
+        let mut exprs = HirExpressions::new(hir_exprs);
+        exprs.voidify();
+        Ok(Hir::sequence(exprs))
+    }
+
     fn convert_ivar_assign(
         &mut self,
         name: &str,
         rhs: &AstExpression,
         is_var: &bool,
     ) -> Result<HirExpression, Error> {
-        let expr = self.convert_expr(rhs)?;
+        // Declare the variable even if `rhs` has an error
+        let expr = self.convert_expr_or_error(rhs);
         let self_ty = self.ctx.self_ty();
 
         if self.ctx.in_initializer() {
@@ -384,21 +1128,55 @@ impl HirMaker {
         arg_exprs: &[AstExpression],
         type_args: &[ConstName],
     ) -> Result<HirExpression, Error> {
-        let arg_hirs = arg_exprs
-            .iter()
-            .map(|arg_expr| self.convert_expr(arg_expr))
-            .collect::<Result<Vec<_>, _>>()?;
+        // The parser ensures the `*' arg is the last one
+        let (arg_exprs, splat_hir) = match arg_exprs.split_last() {
+            Some((last, rest)) => match &last.body {
+                AstExpressionBody::SplatArg { expr } => (rest, Some(self.convert_expr(expr)?)),
+                _ => (arg_exprs, None),
+            },
+            None => (arg_exprs, None),
+        };
+        // Keyword args are converted after the method is found
+        let has_keyword_args = arg_exprs.iter().any(|arg| arg.is_keyword_arg());
+        // So is the block (to infer the types of its params)
+        let (arg_exprs, block_expr) = match arg_exprs.split_last() {
+            Some((last, rest)) if last.is_block() => (rest, Some(last)),
+            _ => (arg_exprs, None),
+        };
+        let mut arg_hirs = if has_keyword_args {
+            vec![]
+        } else {
+            arg_exprs
+                .iter()
+                .map(|arg_expr| self.convert_expr(arg_expr))
+                .collect::<Result<Vec<_>, _>>()?
+        };
 
         // Check if this is a lambda invocation
         if receiver_expr.is_none() {
             if let Some(lvar) = self._lookup_var(&method_name.0) {
                 if let Some(ret_ty) = lvar.ty().fn_x_info() {
+                    if has_keyword_args {
+                        return Err(error::program_error(&format!(
+                            "keyword args cannot be passed to a lambda (`{}')",
+                            method_name
+                        )));
+                    }
+                    if splat_hir.is_some() {
+                        return Err(error::program_error(&format!(
+                            "`*' arg cannot be passed to a lambda (`{}')",
+                            method_name
+                        )));
+                    }
+                    if let Some(block) = block_expr {
+                        arg_hirs.push(self.convert_expr(block)?);
+                    }
                     return Ok(Hir::lambda_invocation(ret_ty, lvar.ref_expr(), arg_hirs));
                 }
             }
         }
 
-        let receiver_hir = match receiver_expr {
+        let mut receiver_hir = match receiver_expr {
             Some(expr) => self.convert_expr(&expr)?,
             // Implicit self
             _ => self.convert_self_expr()?,
@@ -407,7 +1185,245 @@ impl HirMaker {
         for const_name in type_args {
             method_tyargs.push(self._resolve_method_tyarg(const_name)?);
         }
-        self._make_method_call(receiver_hir, &method_name, arg_hirs, &method_tyargs)
+        // Assignments to the temporary variables which hold the receiver
+        // and the keyword args
+        let mut tmp_assigns = vec![];
+        if has_keyword_args {
+            if receiver_hir.ty.is_error_type() {
+                return Ok(Hir::error_expression());
+            }
+            let (sig, _) =
+                self.class_dict
+                    .lookup_method(&receiver_hir.ty, method_name, &method_tyargs)?;
+            let order = self._sort_keyword_args(&sig, arg_exprs, block_expr.is_some())?;
+            // Evaluate the args in the order written
+            let mut given_hirs = vec![];
+            for arg_expr in arg_exprs {
+                let expr = match &arg_expr.body {
+                    AstExpressionBody::KeywordArg { value, .. } => value,
+                    _ => arg_expr,
+                };
+                given_hirs.push(self.convert_expr(expr)?);
+            }
+            // Store them in temporary variables if they are reordered
+            let given_idxs = order.iter().filter_map(|x| *x).collect::<Vec<_>>();
+            if given_idxs.windows(2).any(|w| w[0] > w[1]) {
+                if receiver_expr.is_some() {
+                    let (assign, lvar_ref) = self._store_tmp(receiver_hir);
+                    tmp_assigns.push(assign);
+                    receiver_hir = lvar_ref;
+                }
+                let mut lvar_refs = vec![];
+                for arg_hir in given_hirs {
+                    let (assign, lvar_ref) = self._store_tmp(arg_hir);
+                    tmp_assigns.push(assign);
+                    lvar_refs.push(lvar_ref);
+                }
+                given_hirs = lvar_refs;
+            }
+            let mut given_hirs = given_hirs.into_iter().map(Some).collect::<Vec<_>>();
+            for (idx, param) in order.iter().zip(sig.params.iter()) {
+                let arg_hir = match idx {
+                    Some(i) => given_hirs[*i].take().unwrap(),
+                    None => Hir::omitted_arg(param.ty.clone()),
+                };
+                arg_hirs.push(arg_hir);
+            }
+        }
+        if let Some(block) = block_expr {
+            let block_hir =
+                self._convert_arg(&receiver_hir, method_name, &method_tyargs, &arg_hirs, block)?;
+            arg_hirs.push(block_hir);
+        }
+        let call_hir = self._make_method_call_(
+            receiver_hir,
+            &method_name,
+            arg_hirs,
+            splat_hir,
+            &method_tyargs,
+        )?;
+        if tmp_assigns.is_empty() {
+            Ok(call_hir)
+        } else {
+            tmp_assigns.push(call_hir);
+            Ok(Hir::sequence(HirExpressions::new(tmp_assigns)))
+        }
+    }
+
+    /// Store `expr` in a new temporary variable.
+    /// Returns the assignment and a reference to the variable
+    fn _store_tmp(&mut self, expr: HirExpression) -> (HirExpression, HirExpression) {
+        let name = self.generate_tmp_name("arg");
+        let ty = expr.ty.clone();
+        self.ctx.declare_lvar(&name, ty.clone(), true);
+        (Hir::lvar_assign(&name, expr), Hir::lvar_ref(ty, name))
+    }
+
+    /// Convert an arg which is given after the preceding ones (`arg_hirs`).
+    /// If it is a block, the types of its params may be omitted
+    fn _convert_arg(
+        &mut self,
+        receiver_hir: &HirExpression,
+        method_name: &MethodFirstname,
+        method_tyargs: &[TermTy],
+        arg_hirs: &[HirExpression],
+        arg_expr: &AstExpression,
+    ) -> Result<HirExpression, Error> {
+        match &arg_expr.body {
+            AstExpressionBody::LambdaExpr {
+                params,
+                exprs,
+                is_fn: false,
+            } => {
+                if receiver_hir.ty.is_error_type() {
+                    return Ok(Hir::error_expression());
+                }
+                let (expected_tys, expected_ret_ty) = self._expected_block_tys(
+                    &receiver_hir.ty,
+                    method_name,
+                    method_tyargs,
+                    arg_hirs,
+                )?;
+                self.convert_lambda_expr(
+                    params,
+                    exprs,
+                    false,
+                    &expected_tys,
+                    expected_ret_ty.as_ref(),
+                )
+            }
+            _ => self.convert_expr(arg_expr),
+        }
+    }
+
+    /// Return the types of the params and the return value of the block
+    /// passed to the method after `arg_hirs`. None for the ones which cannot
+    /// be decided (i.e. depends on a method type argument which is not given
+    /// nor inferred from `arg_hirs`)
+    fn _expected_block_tys(
+        &self,
+        receiver_ty: &TermTy,
+        method_name: &MethodFirstname,
+        method_tyargs: &[TermTy],
+        arg_hirs: &[HirExpression],
+    ) -> Result<(Vec<Option<TermTy>>, Option<TermTy>), Error> {
+        let (sig, found_class_name) =
+            self.class_dict
+                .lookup_method(receiver_ty, method_name, method_tyargs)?;
+        let idx = arg_hirs.len();
+        if !method_tyargs.is_empty() || sig.typarams.is_empty() {
+            let tys = fn_tyargs(&sig, idx).into_iter().map(Some).collect();
+            return Ok(split_ret_ty(tys));
+        }
+
+        let base_sig = self
+            .class_dict
+            .find_method(&found_class_name, method_name)
+            .expect("[BUG] method not found");
+        let arg_tys = arg_hirs.iter().map(|expr| &expr.ty).collect::<Vec<_>>();
+        let partial = type_inference::infer_partially(&self.class_dict, base_sig, &arg_tys, None)?;
+        // Leave the unknown ones as type parameters
+        let tyargs = partial
+            .iter()
+            .zip(base_sig.typarams.iter())
+            .enumerate()
+            .map(|(i, (tyarg, typaram))| {
+                tyarg
+                    .clone()
+                    .unwrap_or_else(|| ty::typaram(typaram, TyParamKind::Method, i))
+            })
+            .collect::<Vec<_>>();
+        let (sig, _) = self
+            .class_dict
+            .lookup_method(receiver_ty, method_name, &tyargs)?;
+        let tys = fn_tyargs(&sig, idx)
+            .into_iter()
+            .zip(fn_tyargs(base_sig, idx))
+            .map(|(ty, base_ty)| {
+                if type_inference::has_unknown_tyarg(&base_ty, &partial) {
+                    None
+                } else {
+                    Some(ty)
+                }
+            })
+            .collect();
+        Ok(split_ret_ty(tys))
+    }
+
+    /// Match the args with the params by the names. Returns the index of
+    /// the arg in `arg_exprs` for each param, or None for the omitted ones
+    /// (which have a default value). The last param is excluded if
+    /// `has_block` is true (i.e. the block is given separately)
+    /// eg. `foo(1, c: 3, b: 2)` => `[Some(0), Some(2), Some(1)]`
+    fn _sort_keyword_args(
+        &self,
+        sig: &MethodSignature,
+        arg_exprs: &[AstExpression],
+        has_block: bool,
+    ) -> Result<Vec<Option<usize>>, Error> {
+        if sig.rest_param_idx().is_some() {
+            return Err(error::type_error(&format!(
+                "keyword args cannot be passed to {} because it has a rest param",
+                sig.fullname
+            )));
+        }
+        let n_params = if has_block {
+            sig.params.len().saturating_sub(1)
+        } else {
+            sig.params.len()
+        };
+        let params = &sig.params[..n_params];
+        let mut order = vec![None; n_params];
+        let mut seen_keyword = false;
+        for (i, arg_expr) in arg_exprs.iter().enumerate() {
+            let (idx, name) = if let AstExpressionBody::KeywordArg { name, .. } = &arg_expr.body {
+                seen_keyword = true;
+                // `@` may be omitted for `initialize(@a: Int)`
+                let found = signature::find_param(params, name)
+                    .or_else(|| signature::find_param(params, &format!("@{}", name)));
+                match found {
+                    Some((idx, param)) => (idx, &param.name),
+                    None => {
+                        return Err(error::type_error(&format!(
+                            "{} does not have a param named `{}'",
+                            sig.fullname, name
+                        )))
+                    }
+                }
+            } else {
+                if seen_keyword {
+                    return Err(error::program_error(
+                        "positional args cannot follow keyword args",
+                    ));
+                }
+                match params.get(i) {
+                    Some(param) => (i, &param.name),
+                    None => {
+                        return Err(error::type_error(&format!(
+                            "{} takes {} args but got more",
+                            sig.fullname, n_params
+                        )))
+                    }
+                }
+            };
+            if order[idx].is_some() {
+                return Err(error::type_error(&format!(
+                    "the argument `{}' of `{}' is given more than once",
+                    name, sig.fullname
+                )));
+            }
+            order[idx] = Some(i);
+        }
+
+        for (idx, param) in order.iter().zip(params.iter()) {
+            if idx.is_none() && param.default_expr.is_none() {
+                return Err(error::type_error(&format!(
+                    "the argument `{}' of `{}' is missing",
+                    param.name, sig.fullname
+                )));
+            }
+        }
+        Ok(order)
     }
 
     /// Resolve a method tyarg (a ConstName) into a TermTy
@@ -423,20 +1439,65 @@ impl HirMaker {
     }
 
     /// Resolve the method and create HirMethodCall
-    fn _make_method_call(
-        &self,
+    pub(super) fn _make_method_call(
+        &mut self,
+        receiver_hir: HirExpression,
+        method_name: &MethodFirstname,
+        arg_hirs: Vec<HirExpression>,
+        method_tyargs: &[TermTy],
+    ) -> Result<HirExpression, Error> {
+        self._make_method_call_(receiver_hir, method_name, arg_hirs, None, method_tyargs)
+    }
+
+    /// `splat_hir` is the arg given with `*` (eg. `ary` of `foo(1, *ary)`)
+    fn _make_method_call_(
+        &mut self,
         receiver_hir: HirExpression,
         method_name: &MethodFirstname,
         mut arg_hirs: Vec<HirExpression>,
+        splat_hir: Option<HirExpression>,
         method_tyargs: &[TermTy],
     ) -> Result<HirExpression, Error> {
+        if receiver_hir.ty.is_error_type() {
+            return Ok(Hir::error_expression());
+        }
         let specialized = receiver_hir.ty.is_specialized();
         let class_fullname = &receiver_hir.ty.fullname;
-        let (sig, found_class_name) =
+        let (mut sig, found_class_name) =
             self.class_dict
                 .lookup_method(&receiver_hir.ty, method_name, method_tyargs)?;
 
-        let arg_tys = arg_hirs.iter().map(|expr| &expr.ty).collect::<Vec<_>>();
+        // Omitted args (given by `_sort_keyword_args`) are skipped in
+        // type inference and type checking
+        let omitted_ty = ty::error();
+        let arg_tys = arg_hirs
+            .iter()
+            .map(|expr| {
+                if expr.is_omitted_arg() {
+                    &omitted_ty
+                } else {
+                    &expr.ty
+                }
+            })
+            .collect::<Vec<_>>();
+        if method_tyargs.is_empty() && !sig.typarams.is_empty() {
+            // Infer the type arguments (eg. `U` of `ary.map{|x: Int| x.to_s}`)
+            // from the signature before applying the receiver's type arguments
+            let base_sig = self
+                .class_dict
+                .find_method(&found_class_name, method_name)
+                .expect("[BUG] method not found");
+            let inferred = type_inference::infer_method_tyargs(
+                &self.class_dict,
+                base_sig,
+                &arg_tys,
+                splat_hir.as_ref().map(|splat| &splat.ty),
+            )?;
+            sig = self
+                .class_dict
+                .lookup_method(&receiver_hir.ty, method_name, &inferred)?
+                .0;
+        }
         type_checking::check_method_args(
             &self.class_dict,
             &sig,
@@ -447,6 +1508,16 @@ impl HirMaker {
         if let Some(last_arg) = arg_hirs.last_mut() {
             check_break_in_block(&sig, last_arg)?;
         }
+        if let Some(splat) = splat_hir {
+            type_checking::check_splat_arg(&self.class_dict, &sig, arg_hirs.len(), &splat.ty)?;
+            fill_omitted_args(&sig, &mut arg_hirs);
+            // Pass a copy so that the callee cannot modify the array of the caller
+            let copied = self._make_method_call(splat, &method_firstname("clone"), vec![], &[])?;
+            arg_hirs.push(copied);
+        } else {
+            fill_omitted_args(&sig, &mut arg_hirs);
+            self._pack_rest_args(&sig, &mut arg_hirs);
+        }
 
         let receiver = if &found_class_name != class_fullname {
             // Upcast needed
@@ -471,31 +1542,105 @@ impl HirMaker {
         Ok(ret)
     }
 
+    /// Pack the args for the rest param into an array
+    fn _pack_rest_args(&self, sig: &MethodSignature, arg_hirs: &mut Vec<HirExpression>) {
+        if let Some(idx) = sig.rest_param_idx() {
+            let rest_args = arg_hirs.split_off(idx);
+            arg_hirs.push(Hir::array_literal(rest_args, sig.params[idx].ty.clone()));
+        }
+    }
+
+    /// `expected_tys` are the param types expected by the method which
+    /// takes this lambda as a block (None if unknown). The types of the
+    /// params can be omitted if expected.
+    /// `expected_ret_ty` is the return type of the block expected by the
+    /// method, if known
     fn convert_lambda_expr(
         &mut self,
         params: &[ast::Param],
         exprs: &[AstExpression],
-        is_fn: &bool,
+        is_fn: bool,
+        expected_tys: &[Option<TermTy>],
+        expected_ret_ty: Option<&TermTy>,
+    ) -> Result<HirExpression, Error> {
+        let class_typarams = self.current_class_typarams();
+        let method_typarams = self.current_method_typarams();
+        let mut hir_params = vec![];
+        let mut fn_tyargs = vec![];
+        for (i, param) in params.iter().enumerate() {
+            let expected = expected_tys.get(i).and_then(|t| t.as_ref());
+            let ty = match (&param.typ, expected) {
+                (Some(typ), _) => {
+                    let ty = signature::convert_typ(typ, &class_typarams, &method_typarams);
+                    // eg. `|x: Object|` is ok for `Fn1<Int, ...>`
+                    if let Some(expected) = expected {
+                        if !expected.conforms_to(&ty, &self.class_dict) {
+                            return Err(error::type_error(&format!(
+                                "the param `{}' of the block should accept {} but declared as {}",
+                                param.name, expected, ty
+                            )));
+                        }
+                    }
+                    ty
+                }
+                (None, Some(expected)) => expected.clone(),
+                (None, None) => {
+                    return Err(error::type_error(&format!(
+                        "the type of the param `{}' cannot be inferred (please specify it like `{}: Int')",
+                        param.name, param.name
+                    )))
+                }
+            };
+            fn_tyargs.push(expected.unwrap_or(&ty).clone());
+            hir_params.push(MethodParam {
+                name: param.name.to_string(),
+                ty,
+                default_expr: None,
+                is_rest: false,
+            });
+        }
+
+        let lambda_ctx = LambdaCtx::new(is_fn, hir_params);
+        let mut lambda = self.convert_lambda_expr_(lambda_ctx, exprs, expected_ret_ty)?;
+        // The lambda is passed as the expected type even if the params are
+        // declared as wider types
+        if let Some(ret_ty) = lambda.ty.fn_x_info() {
+            fn_tyargs.push(ret_ty);
+            lambda.ty = ty::spe(&format!("Fn{}", params.len()), fn_tyargs);
+        }
+        Ok(lambda)
+    }
+
+    fn convert_lambda_expr_(
+        &mut self,
+        lambda_ctx: LambdaCtx,
+        exprs: &[AstExpression],
+        expected_ret_ty: Option<&TermTy>,
     ) -> Result<HirExpression, Error> {
         self.lambda_ct += 1;
         let lambda_id = self.lambda_ct;
-        let hir_params = signature::convert_params(
-            params,
-            &self.current_class_typarams(),
-            &self.current_method_typarams(),
-        );
 
         // Convert lambda body
-        self.ctx
-            .lambdas
-            .push(LambdaCtx::new(*is_fn, hir_params.clone()));
+        let hir_params = lambda_ctx.params.clone();
+        self.ctx.lambdas.push(lambda_ctx);
 
+        // Narrowing is not applied in lambdas because the parameters may
+        // shadow the narrowed variables
+        let narrowed_lvars = std::mem::take(&mut self.ctx.narrowed_lvars);
         let mut current = CtxKind::Lambda;
         self.ctx.swap_current(&mut current);
-        let hir_exprs = self.convert_exprs(exprs)?;
+        let hir_exprs = self.convert_exprs(exprs);
         self.ctx.swap_current(&mut current);
+        self.ctx.narrowed_lvars = narrowed_lvars;
+        let hir_exprs = hir_exprs?;
 
         let mut lambda_ctx = self.ctx.lambdas.pop().unwrap();
+        type_checking::check_next_arg_tys(
+            &self.class_dict,
+            &lambda_ctx.next_arg_tys,
+            &hir_exprs.ty,
+            expected_ret_ty,
+        )?;
         Ok(Hir::lambda_expr(
             lambda_ty(&hir_params, &hir_exprs.ty), // ty
             format!("lambda_{}", lambda_id),       // name
@@ -542,7 +1687,11 @@ impl HirMaker {
     /// Generate local variable reference or method call with implicit receiver(self)
     fn convert_bare_name(&mut self, name: &str) -> Result<HirExpression, Error> {
         if let Some(lvar_info) = self._find_var(name, false)? {
-            Ok(lvar_info.ref_expr())
+            let ref_expr = lvar_info.ref_expr();
+            match self.ctx.narrowed_lvars.get(name) {
+                Some(narrowed_ty) => Ok(Hir::bit_cast(narrowed_ty.clone(), ref_expr)),
+                None => Ok(ref_expr),
+            }
         } else {
             Err(error::program_error(&format!(
                 "variable `{}' was not found",
@@ -579,7 +1728,7 @@ impl HirMaker {
                 if first {
                     ret = Some(LVarInfo::CurrentScope {
                         ty: lvar.ty.clone(),
-                        name: name.to_string(),
+                        name: lvar.name.clone(),
                     });
                     break;
                 } else {
@@ -589,7 +1738,7 @@ impl HirMaker {
                         ctx_depth: depth,
                         ty: lvar.ty.clone(),
                         detail: LambdaCaptureDetail::CapLVar {
-                            name: name.to_string(),
+                            name: lvar.name.clone(),
                         },
                     });
                     ret = Some(LVarInfo::OuterScope {
@@ -660,25 +1809,38 @@ impl HirMaker {
     }
 
     /// Resolve constant name
-    fn convert_const_ref(&mut self, name: &ConstName) -> Result<HirExpression, Error> {
+    pub(super) fn convert_const_ref(&mut self, name: &ConstName) -> Result<HirExpression, Error> {
         if let Some((ty, fullname)) = self._lookup_const(name) {
             return Ok(Hir::const_ref(ty.clone(), fullname));
         }
         // Check if it refers to a class
+        let name = &self._resolve_class_name(name);
         self._check_class_exists(name)?;
         let class_ty = self._create_class_const(name);
         Ok(Hir::const_ref(class_ty, name.to_const_fullname()))
     }
 
-    /// Lookup a constant from current scope
+    /// Lookup a constant from current scope (and its outer scopes)
     fn _lookup_const(&self, name: &ConstName) -> Option<(&TermTy, ConstFullname)> {
-        let fullname = name.to_const_fullname();
-        if let Some(found) = self.constants.get(&fullname) {
-            return Some((found, fullname));
-        }
+        enclosing_namespaces(self.ctx.const_namespace())
+            .iter()
+            .map(|namespace| name.under_namespace(namespace))
+            .find_map(|fullname| self.constants.get(&fullname).map(|found| (found, fullname)))
+    }
 
-        let fullname = name.under_namespace(&self.ctx.namespace());
-        self.constants.get(&fullname).map(|found| (found, fullname))
+    /// Resolve a class name from current scope (and its outer scopes)
+    /// eg. `B` in `module A` may be `A::B`
+    fn _resolve_class_name(&self, name: &ConstName) -> ConstName {
+        let s = name.names.join("::");
+        enclosing_namespaces(self.ctx.const_namespace())
+            .iter()
+            .map(|namespace| class_firstname(&s).add_namespace(namespace))
+            .find(|fullname| self.class_dict.class_exists(&fullname.0))
+            .map(|fullname| ConstName {
+                names: fullname.0.split("::").map(|s| s.to_string()).collect(),
+                args: name.args.clone(),
+            })
+            .unwrap_or_else(|| name.clone())
     }
 
     /// Check `name` refers proper class name
@@ -696,7 +1858,7 @@ impl HirMaker {
         let mut typarams = self.current_class_typarams();
         typarams.append(&mut self.current_method_typarams());
         for arg in &name.args {
-            if typarams.contains(&arg.string()) {
+            if typarams.iter().any(|p| p.name == arg.string()) {
                 // ok.
             } else {
                 self._check_class_exists(arg)?;
@@ -723,7 +1885,7 @@ impl HirMaker {
 
     /// Create `Meta:A<B>` when there is a const `A<B>`
     /// Return class_ty
-    fn _create_specialized_meta_class(&mut self, name: &ConstName) -> TermTy {
+    pub(super) fn _create_specialized_meta_class(&mut self, name: &ConstName) -> TermTy {
         let mut ivars = HashMap::new();
         ivars.insert(
             "name".to_string(),
@@ -780,22 +1942,92 @@ impl HirMaker {
         item_exprs: Vec<HirExpression>,
     ) -> Result<HirExpression, Error> {
         // TODO #102: Support empty array literal
-        let mut item_ty = if item_exprs.is_empty() {
+        let item_ty = self._common_ty(&item_exprs);
+        let ary_ty = ty::spe("Array", vec![item_ty]);
+
+        Ok(Hir::array_literal(item_exprs, ary_ty))
+    }
+
+    /// `(a, b)` is expanded into `Tuple2.literal<A, B>(a, b)`
+    fn convert_tuple_literal(&mut self, exprs: &[AstExpression]) -> Result<HirExpression, Error> {
+        if exprs.len() > ty::MAX_TUPLE_SIZE {
+            return Err(error::program_error(&format!(
+                "tuple with more than {} elements is not supported",
+                ty::MAX_TUPLE_SIZE
+            )));
+        }
+        let item_hirs = exprs
+            .iter()
+            .map(|expr| self.convert_expr(expr))
+            .collect::<Result<Vec<_>, _>>()?;
+        let item_tys = item_hirs.iter().map(|x| x.ty.clone()).collect::<Vec<_>>();
+        let cls = self.convert_const_ref(&const_name(vec![ty::tuple_class_name(exprs.len())]))?;
+        self._make_method_call(cls, &method_firstname("literal"), item_hirs, &item_tys)
+    }
+
+    /// `{k1 => v1, k2 => v2}` is expanded into
+    /// `Hash.literal<K, V>([(k1, v1), (k2, v2)])`
+    fn convert_hash_literal(
+        &mut self,
+        pairs: &[(AstExpression, AstExpression)],
+    ) -> Result<HirExpression, Error> {
+        let mut key_hirs = vec![];
+        let mut value_hirs = vec![];
+        for (key, value) in pairs {
+            key_hirs.push(self.convert_expr(key)?);
+            value_hirs.push(self.convert_expr(value)?);
+        }
+        // TODO #102: Support empty hash literal
+        let key_ty = self._common_ty(&key_hirs);
+        let value_ty = self._common_ty(&value_hirs);
+        let tuple_ty = ty::spe(
+            &ty::tuple_class_name(2),
+            vec![key_ty.clone(), value_ty.clone()],
+        );
+        let mut tuple_hirs = vec![];
+        for (key_hir, value_hir) in key_hirs.into_iter().zip(value_hirs) {
+            let tuple_cls = self.convert_const_ref(&const_name(vec![ty::tuple_class_name(2)]))?;
+            tuple_hirs.push(self._make_method_call(
+                tuple_cls,
+                &method_firstname("literal"),
+                vec![key_hir, value_hir],
+                &[key_ty.clone(), value_ty.clone()],
+            )?);
+        }
+        let ary = Hir::array_literal(tuple_hirs, ty::ary(tuple_ty));
+        let cls = self.convert_const_ref(&const_name(vec!["Hash".to_string()]))?;
+        self._make_method_call(
+            cls,
+            &method_firstname("literal"),
+            vec![ary],
+            &[key_ty, value_ty],
+        )
+    }
+
+    /// Return the nearest common ancestor of the types of `exprs`
+    /// (`Object` if `exprs` is empty)
+    fn _common_ty(&self, exprs: &[HirExpression]) -> TermTy {
+        let mut ty = if exprs.is_empty() {
             ty::raw("Object")
         } else {
-            item_exprs[0].ty.clone()
+            exprs[0].ty.clone()
         };
-
-        for expr in &item_exprs {
-            item_ty = self.nearest_common_ancestor_type(&item_ty, &expr.ty)
+        for expr in exprs {
+            ty = self.nearest_common_ancestor_type(&ty, &expr.ty)
         }
-        let ary_ty = ty::spe("Array", vec![item_ty]);
-
-        Ok(Hir::array_literal(item_exprs, ary_ty))
+        ty
     }
 
+    /// In the instance methods of a generic class `Foo<T>`, `self` is a `Foo<T>`
     fn convert_self_expr(&self) -> Result<HirExpression, Error> {
-        Ok(Hir::self_expression(self.ctx.self_ty()))
+        let self_ty = self.ctx.self_ty();
+        let typarams = self.current_class_typarams();
+        if typarams.is_empty() {
+            Ok(Hir::self_expression(self_ty))
+        } else {
+            let ty = ty::return_type_of_new(&self_ty.fullname, &typarams);
+            Ok(Hir::self_expression(ty))
+        }
     }
 
     fn convert_string_literal(&mut self, content: &str) -> Result<HirExpression, Error> {
@@ -811,14 +2043,56 @@ impl HirMaker {
 
     /// Return the nearest common ancestor of the classes
     fn nearest_common_ancestor_type(&self, ty1: &TermTy, ty2: &TermTy) -> TermTy {
-        let ancestors1 = self.class_dict.ancestor_types(ty1);
-        let ancestors2 = self.class_dict.ancestor_types(ty2);
-        for t2 in ancestors2 {
-            if let Some(eq) = ancestors1.iter().find(|t1| t1.equals_to(&t2)) {
-                return eq.clone();
-            }
+        if ty1.is_error_type() {
+            return ty2.clone();
+        } else if ty2.is_error_type() {
+            return ty1.clone();
+        }
+        let t1 = self._nearest_ancestor_conformed_by(ty1, ty2);
+        let t2 = self._nearest_ancestor_conformed_by(ty2, ty1);
+        if t2.conforms_to(&t1, &self.class_dict) {
+            t2
+        } else {
+            t1
+        }
+    }
+
+    /// Return the nearest ancestor of `ty1` which `ty2` conforms to
+    fn _nearest_ancestor_conformed_by(&self, ty1: &TermTy, ty2: &TermTy) -> TermTy {
+        self.class_dict
+            .ancestor_types(ty1)
+            .into_iter()
+            .find(|t| ty2.conforms_to(t, &self.class_dict))
+            .expect("[BUG] nearest_common_ancestor_type not found")
+    }
+}
+
+/// Return the type arguments of the `FnN` at `idx` of the params
+/// (empty if not a `FnN`)
+fn fn_tyargs(sig: &MethodSignature, idx: usize) -> Vec<TermTy> {
+    match sig.params.get(idx) {
+        Some(param) if param.ty.fn_x_info().is_some() => match &param.ty.body {
+            TyBody::TySpe { type_args, .. } => type_args.clone(),
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+/// Split the type arguments of a `FnN` into the param types and the return type
+fn split_ret_ty(mut tys: Vec<Option<TermTy>>) -> (Vec<Option<TermTy>>, Option<TermTy>) {
+    let ret_ty = tys.pop().and_then(|ty| ty);
+    (tys, ret_ty)
+}
+
+/// Append the omitted args, whose default values are computed by the callee
+/// (the rest param does not have a default value)
+fn fill_omitted_args(sig: &MethodSignature, arg_hirs: &mut Vec<HirExpression>) {
+    let n_params = sig.rest_param_idx().unwrap_or_else(|| sig.params.len());
+    if arg_hirs.len() < n_params {
+        for param in &sig.params[arg_hirs.len()..n_params] {
+            arg_hirs.push(Hir::omitted_arg(param.ty.clone()));
         }
-        panic!("[BUG] nearest_common_ancestor_type not found");
     }
 }
 
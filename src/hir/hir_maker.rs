@@ -1,5 +1,6 @@
 use crate::ast::*;
 use crate::code_gen::CodeGen;
+use crate::error;
 use crate::error::Error;
 use crate::hir::class_dict::ClassDict;
 use crate::hir::hir_maker_context::*;
@@ -24,9 +25,14 @@ pub struct HirMaker {
     pub(super) ctx: HirMakerContext,
     /// Counter to give unique name for lambdas
     pub(super) lambda_ct: usize,
+    /// Counter to give unique name for temporary lvars
+    pub(super) tmp_ct: usize,
+    /// Errors found so far (reported after the whole program is processed)
+    pub(super) errors: Vec<Error>,
 }
 
 pub fn make_hir(ast: ast::Program, corelib: Corelib) -> Result<Hir, Error> {
+    let ast = mixin::expand_includes(ast)?;
     let class_dict = class_dict::create(&ast, corelib.sk_classes)?;
     let mut hir = convert_program(class_dict, ast)?;
 
@@ -40,6 +46,11 @@ pub fn make_hir(ast: ast::Program, corelib: Corelib) -> Result<Hir, Error> {
 fn convert_program(class_dict: ClassDict, prog: ast::Program) -> Result<Hir, Error> {
     let mut hir_maker = HirMaker::new(class_dict);
     let (main_exprs, main_lvars) = hir_maker.convert_toplevel_items(&prog.toplevel_items)?;
+    hir_maker.define_class_objects();
+    if !hir_maker.errors.is_empty() {
+        let errs = std::mem::take(&mut hir_maker.errors);
+        return Err(error::errors(errs));
+    }
     Ok(hir_maker.extract_hir(main_exprs, main_lvars))
 }
 
@@ -53,9 +64,19 @@ impl HirMaker {
             str_literals: vec![],
             ctx: HirMakerContext::new(),
             lambda_ct: 0,
+            tmp_ct: 0,
+            errors: vec![],
         }
     }
 
+    /// Generate a name for a temporary lvar
+    /// (which never conflicts with user-defined lvars)
+    pub(super) fn generate_tmp_name(&mut self, prefix: &str) -> String {
+        let name = format!("{}@{}", prefix, self.tmp_ct);
+        self.tmp_ct += 1;
+        name
+    }
+
     /// Destructively convert self to Hir
     fn extract_hir(&mut self, main_exprs: HirExpressions, main_lvars: HirLVars) -> Hir {
         // Extract data from self
@@ -90,10 +111,15 @@ impl HirMaker {
         for item in items {
             match item {
                 ast::TopLevelItem::Def(def) => {
-                    self.process_toplevel_def(&def)?;
+                    if let Err(e) = self.process_toplevel_def(&def) {
+                        self.errors.push(e.located_at(&def.span()));
+                    }
                 }
                 ast::TopLevelItem::Expr(expr) => {
-                    main_exprs.push(self.convert_expr(&expr)?);
+                    main_exprs.push(self.convert_expr_or_error(&expr));
+                }
+                ast::TopLevelItem::Require(_) => {
+                    panic!("[BUG] require should be resolved by runner")
                 }
             }
         }
@@ -108,11 +134,28 @@ impl HirMaker {
             // Extract instance/class methods
             ast::Definition::ClassDefinition { name, typarams, defs, .. } => {
                 let full = name.add_namespace("");
-                self.process_defs_in_class(&full, typarams.clone(), defs)?;
+                self.process_defs_in_class(&full, signature::convert_typarams(typarams), defs)?;
+            }
+            ast::Definition::EnumDefinition {
+                name,
+                typarams,
+                cases,
+                defs,
+                ..
+            } => {
+                let full = name.add_namespace("");
+                self.process_enum_def(&full, typarams, cases, defs)?;
+            }
+            ast::Definition::ModuleDefinition { name, defs, .. } => {
+                let full = name.add_namespace("");
+                self.process_module_def(&full, defs)?;
             }
             ast::Definition::ConstDefinition { name, expr } => {
                 self.register_const(name, expr)?;
             }
+            ast::Definition::InterfaceDefinition { .. } => {
+                // Nothing to compile (indexed in hir::class_dict)
+            }
             _ => panic!("should be checked in hir::class_dict"),
         }
         Ok(())
@@ -122,7 +165,7 @@ impl HirMaker {
     fn process_defs_in_class(
         &mut self,
         fullname: &ClassFullname,
-        typarams: Vec<String>,
+        typarams: Vec<TyParam>,
         defs: &[ast::Definition],
     ) -> Result<(), Error> {
         let meta_name = fullname.meta_name();
@@ -138,40 +181,84 @@ impl HirMaker {
             self._process_initialize(fullname, defs.iter().find(|d| d.is_initializer()))?;
         if !own_ivars.is_empty() {
             // Be careful not to reset ivars of corelib/* by builtin/*
-            self.class_dict.define_ivars(fullname, own_ivars.clone())?;
+            if let Err(e) = self.class_dict.define_ivars(fullname, own_ivars.clone()) {
+                self.errors.push(e);
+            }
             self.define_accessors(fullname, own_ivars, defs);
+        } else if self._inherits_ivars(fullname) {
+            // eg. `class RuntimeError : StandardError` without #initialize
+            if let Err(e) = self.class_dict.define_ivars(fullname, HashMap::new()) {
+                self.errors.push(e);
+            }
         }
 
         // Register .new
-        self.method_dict
-            .add_method(&meta_name, self.create_new(&fullname)?);
+        match self.create_new(&fullname) {
+            Ok(method) => self.method_dict.add_method(&meta_name, method),
+            Err(e) => self.errors.push(e),
+        }
 
         for def in defs {
             match def {
                 ast::Definition::InstanceMethodDefinition {
-                    sig, body_exprs, ..
+                    sig,
+                    body_exprs,
+                    module,
+                    ..
                 } => {
                     if def.is_initializer() {
                         // Already processed above
                     } else {
                         log::trace!("method {}#{}", &fullname, &sig.name);
-                        let method = self.convert_method_def(&fullname, &sig.name, &body_exprs)?;
-                        self.method_dict.add_method(&fullname, method);
+                        match self.convert_method_def(
+                            &fullname,
+                            &sig.name,
+                            &body_exprs,
+                            module.as_ref(),
+                        ) {
+                            Ok(method) => self.method_dict.add_method(&fullname, method),
+                            Err(e) => self.errors.push(e.located_at(&def.span())),
+                        }
                     }
                 }
                 ast::Definition::ClassMethodDefinition {
                     sig, body_exprs, ..
                 } => {
                     log::trace!("method {}.{}", &fullname, &sig.name);
-                    let method = self.convert_method_def(&meta_name, &sig.name, &body_exprs)?;
-                    self.method_dict.add_method(&meta_name, method);
+                    match self.convert_method_def(&meta_name, &sig.name, &body_exprs, None) {
+                        Ok(method) => self.method_dict.add_method(&meta_name, method),
+                        Err(e) => self.errors.push(e.located_at(&def.span())),
+                    }
                 }
                 ast::Definition::ConstDefinition { .. } => {
                     // Already processed above
                 }
                 ast::Definition::ClassDefinition { name, defs, typarams, .. } => {
                     let full = name.add_namespace(&fullname.0);
-                    self.process_defs_in_class(&full, typarams.clone(), defs)?;
+                    self.process_defs_in_class(&full, signature::convert_typarams(typarams), defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::EnumDefinition {
+                    name,
+                    typarams,
+                    cases,
+                    defs,
+                    ..
+                } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.process_enum_def(&full, typarams, cases, defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::ModuleDefinition { name, defs, .. } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.process_module_def(&full, defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::IncludeDefinition { .. } => {
+                    // Already expanded by hir::mixin
+                }
+                ast::Definition::InterfaceDefinition { .. } => {
+                    // Nothing to compile (indexed in hir::class_dict)
                 }
             }
         }
@@ -180,6 +267,115 @@ impl HirMaker {
         Ok(())
     }
 
+    /// Process the class methods, constants and inner classes of a module
+    fn process_module_def(
+        &mut self,
+        fullname: &ClassFullname,
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        let meta_name = fullname.meta_name();
+        let mut current = CtxKind::Class;
+        self.ctx.swap_current(&mut current);
+        self.ctx.classes.push(ClassCtx::new(fullname.clone(), vec![]));
+
+        self._process_const_defs_in_class(defs, fullname)?;
+        for def in defs {
+            match def {
+                ast::Definition::ClassMethodDefinition {
+                    sig, body_exprs, ..
+                } => {
+                    log::trace!("method {}.{}", &fullname, &sig.name);
+                    match self.convert_method_def(&meta_name, &sig.name, &body_exprs, None) {
+                        Ok(method) => self.method_dict.add_method(&meta_name, method),
+                        Err(e) => self.errors.push(e.located_at(&def.span())),
+                    }
+                }
+                ast::Definition::ClassDefinition { name, defs, typarams, .. } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.process_defs_in_class(&full, signature::convert_typarams(typarams), defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::EnumDefinition {
+                    name,
+                    typarams,
+                    cases,
+                    defs,
+                    ..
+                } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.process_enum_def(&full, typarams, cases, defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::ModuleDefinition { name, defs, .. } => {
+                    let full = name.add_namespace(&fullname.0);
+                    self.process_module_def(&full, defs)
+                        .map_err(|e| e.located_at(&def.span()))?;
+                }
+                ast::Definition::InstanceMethodDefinition { .. }
+                | ast::Definition::IncludeDefinition { .. } => {
+                    // Compiled as a part of the classes which include this module
+                }
+                ast::Definition::InterfaceDefinition { .. } => {
+                    // Nothing to compile (indexed in hir::class_dict)
+                }
+                ast::Definition::ConstDefinition { .. } => {
+                    // Already processed above
+                }
+            }
+        }
+        self.ctx.classes.pop();
+        self.ctx.swap_current(&mut current);
+        Ok(())
+    }
+
+    /// Process an enum and its cases
+    fn process_enum_def(
+        &mut self,
+        fullname: &ClassFullname,
+        typarams: &[ast::AstTyParam],
+        cases: &[ast::EnumCase],
+        defs: &[ast::Definition],
+    ) -> Result<(), Error> {
+        let typarams = signature::convert_typarams(typarams);
+        self.process_defs_in_class(fullname, typarams.clone(), defs)?;
+        for case in cases {
+            let case_fullname = case.name.add_namespace(&fullname.0);
+            let case_defs = case.initializer().into_iter().collect::<Vec<_>>();
+            self.process_defs_in_class(&case_fullname, typarams.clone(), &case_defs)?;
+            if case.params.is_empty() {
+                self.register_enum_case_const(&case_fullname, &typarams)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Register the instance of an enum case which has no fields
+    /// eg. `::Expr::Nil` for `case Nil` of `enum Expr<T>`
+    /// (its type is `Expr::Nil<Never>` so that it conforms to any `Expr<X>`)
+    fn register_enum_case_const(
+        &mut self,
+        case_fullname: &ClassFullname,
+        typarams: &[TyParam],
+    ) -> Result<(), Error> {
+        let name = ConstName {
+            names: case_fullname.0.split("::").map(|s| s.to_string()).collect(),
+            args: typarams
+                .iter()
+                .map(|_| names::const_name(vec!["Never".to_string()]))
+                .collect(),
+        };
+        let class_ty = if typarams.is_empty() {
+            case_fullname.class_ty()
+        } else {
+            self._create_specialized_meta_class(&name)
+        };
+        let idx = self.register_string_literal(&name.string());
+        let class_obj = Hir::class_literal(class_ty, &name, idx);
+        let instance = self._make_method_call(class_obj, &method_firstname("new"), vec![], &[])?;
+        self.register_const_full(const_fullname(&format!("::{}", case_fullname.0)), instance);
+        Ok(())
+    }
+
     /// Add `#initialize` and return defined ivars
     fn _process_initialize(
         &mut self,
@@ -188,18 +384,36 @@ impl HirMaker {
     ) -> Result<SkIVars, Error> {
         let mut own_ivars = HashMap::default();
         if let Some(ast::Definition::InstanceMethodDefinition {
-            sig, body_exprs, ..
+            sig,
+            body_exprs,
+            module,
+            ..
         }) = initialize
         {
             log::trace!("method {}#initialize", &fullname);
-            let (sk_method, found_ivars) =
-                self.create_initialize(&fullname, &sig.name, &body_exprs)?;
-            self.method_dict.add_method(&fullname, sk_method);
-            own_ivars = found_ivars;
+            match self.create_initialize(&fullname, &sig.name, &body_exprs, module.as_ref()) {
+                Ok((sk_method, found_ivars)) => {
+                    self.method_dict.add_method(&fullname, sk_method);
+                    own_ivars = found_ivars;
+                }
+                Err(e) => self
+                    .errors
+                    .push(e.located_at(&initialize.and_then(|d| d.span()))),
+            }
         }
         Ok(own_ivars)
     }
 
+    /// Return true if the class has no ivars yet but its superclass has
+    fn _inherits_ivars(&self, fullname: &ClassFullname) -> bool {
+        let cls = self.class_dict.get_class(fullname, "HirMaker::_inherits_ivars");
+        let super_has_ivars = self
+            .class_dict
+            .get_superclass(fullname)
+            .map_or(false, |super_cls| !super_cls.ivars.is_empty());
+        cls.ivars.is_empty() && super_has_ivars
+    }
+
     /// Register constants defined in a class
     fn _process_const_defs_in_class(
         &mut self,
@@ -209,7 +423,7 @@ impl HirMaker {
         for def in defs {
             if let ast::Definition::ConstDefinition { name, expr } = def {
                 let full = name.add_namespace(&fullname.0);
-                let hir_expr = self.convert_expr(expr)?;
+                let hir_expr = self.convert_expr_or_error(expr);
                 self.register_const_full(full, hir_expr);
             }
         }
@@ -223,12 +437,13 @@ impl HirMaker {
         class_fullname: &ClassFullname,
         name: &MethodFirstname,
         body_exprs: &[AstExpression],
+        module: Option<&ClassFullname>,
     ) -> Result<(SkMethod, SkIVars), Error> {
         let super_ivars = self
             .class_dict
             .get_superclass(class_fullname)
             .map(|super_cls| super_cls.ivars.clone());
-        self.convert_method_def_(class_fullname, name, body_exprs, super_ivars)
+        self.convert_method_def_(class_fullname, name, body_exprs, module, super_ivars)
     }
 
     /// Create .new
@@ -279,7 +494,7 @@ impl HirMaker {
     ) -> Result<ConstFullname, Error> {
         // TODO: resolve name using ctx
         let fullname = const_fullname(&format!("{}::{}", self.ctx.namespace(), &name.0));
-        let hir_expr = self.convert_expr(expr)?;
+        let hir_expr = self.convert_expr_or_error(expr);
         Ok(self.register_const_full(fullname, hir_expr))
     }
 
@@ -300,18 +515,21 @@ impl HirMaker {
         class_fullname: &ClassFullname,
         name: &MethodFirstname,
         body_exprs: &[AstExpression],
+        module: Option<&ClassFullname>,
     ) -> Result<SkMethod, Error> {
         let (sk_method, _ivars) =
-            self.convert_method_def_(class_fullname, name, body_exprs, None)?;
+            self.convert_method_def_(class_fullname, name, body_exprs, module, None)?;
         Ok(sk_method)
     }
 
     /// Create a SkMethod and return it with ctx.iivars
+    /// `module` is the module which defines the method, if it is mixed in
     fn convert_method_def_(
         &mut self,
         class_fullname: &ClassFullname,
         name: &MethodFirstname,
         body_exprs: &[AstExpression],
+        module: Option<&ClassFullname>,
         super_ivars: Option<SkIVars>,
     ) -> Result<(SkMethod, HashMap<String, SkIVar>), Error> {
         // MethodSignature is built beforehand by class_dict::new
@@ -325,11 +543,18 @@ impl HirMaker {
             .expect(&err)
             .clone();
 
-        self.ctx.method = Some(MethodCtx::new(signature.clone(), super_ivars));
+        self.check_overridden_defaults(class_fullname, &signature)?;
+        self.ctx.method = Some(MethodCtx::new(
+            signature.clone(),
+            module.cloned(),
+            super_ivars,
+        ));
 
         let mut current = CtxKind::Method;
         self.ctx.swap_current(&mut current);
+        let default_arg_exprs = self.convert_default_args(&signature);
         let mut hir_exprs = self.convert_exprs(body_exprs)?;
+        hir_exprs.exprs.splice(0..0, default_arg_exprs);
         // Insert ::Void so that last expr always matches to ret_ty
         if signature.ret_ty.is_void_type() {
             hir_exprs.voidify();
@@ -350,4 +575,75 @@ impl HirMaker {
             method_ctx.iivars,
         ))
     }
+
+    /// Create the expressions to copy the args which have a default value
+    /// into lvars of the same name, computing the default value if omitted.
+    /// The default values are evaluated in the method (i.e. they can refer
+    /// `self`, ivars and the preceding params)
+    fn convert_default_args(&mut self, signature: &MethodSignature) -> Vec<HirExpression> {
+        let mut exprs = vec![];
+        for (idx, param) in signature.params.iter().enumerate() {
+            let default_expr = match &param.default_expr {
+                Some(expr) => expr,
+                None => continue,
+            };
+            // Hide the succeeding params, which may be omitted too
+            let method_ctx = self.ctx.method.as_mut().unwrap();
+            let all_params = std::mem::replace(
+                &mut method_ctx.signature.params,
+                signature.params[..idx].to_vec(),
+            );
+            let default_hir = self.convert_expr_or_error(default_expr);
+            self.ctx.method.as_mut().unwrap().signature.params = all_params;
+
+            if let Err(e) =
+                type_checking::check_default_value(&self.class_dict, param, &default_hir.ty)
+            {
+                self.errors.push(e.located_at(&default_expr.span));
+            }
+            let value = Hir::arg_or_default(
+                param.ty.clone(),
+                idx,
+                Hir::bit_cast(param.ty.clone(), default_hir),
+            );
+            self.ctx.declare_lvar(&param.name, param.ty.clone(), true);
+            exprs.push(Hir::lvar_assign(&param.name, value));
+        }
+        exprs
+    }
+
+    /// Check that the method has default values for the params which have
+    /// one in the method it overrides (or the interface method it implements)
+    /// because the caller may omit them
+    fn check_overridden_defaults(
+        &self,
+        class_fullname: &ClassFullname,
+        signature: &MethodSignature,
+    ) -> Result<(), Error> {
+        let name = &signature.fullname.first_name;
+        // `#initialize` is only called from `.new` of the class
+        if name.0 == "initialize" {
+            return Ok(());
+        }
+        let mut overridden = vec![];
+        if let Some(super_cls) = self.class_dict.get_superclass(class_fullname) {
+            if let Ok((sig, _)) = self
+                .class_dict
+                .lookup_method(&super_cls.instance_ty, name, &[])
+            {
+                overridden.push(sig);
+            }
+        }
+        let cls = self
+            .class_dict
+            .get_class(class_fullname, "check_overridden_defaults");
+        for iface in &cls.interfaces {
+            if let Some(sig) = self.class_dict.find_method(&iface.vtable_name(), name) {
+                overridden.push(sig.clone());
+            }
+        }
+        overridden
+            .iter()
+            .try_for_each(|sig| type_checking::check_override_defaults(signature, sig))
+    }
 }
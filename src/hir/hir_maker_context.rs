@@ -14,6 +14,10 @@ pub struct HirMakerContext {
     pub classes: Vec<ClassCtx>,
     pub method: Option<MethodCtx>,
     pub lambdas: Vec<LambdaCtx>,
+    /// true if the current `while` has `break`
+    pub while_has_break: bool,
+    /// Variables whose type is narrowed by `if x.is_a(Foo)`
+    pub narrowed_lvars: HashMap<String, TermTy>,
 }
 
 #[derive(Debug)]
@@ -34,14 +38,14 @@ impl ToplevelCtx {
 pub struct ClassCtx {
     /// Current namespace
     pub namespace: ClassFullname,
-    /// Names of class type parameters
-    pub typarams: Vec<String>,
+    /// Class type parameters
+    pub typarams: Vec<TyParam>,
     /// Current local variables
     pub lvars: HashMap<String, CtxLVar>,
 }
 
 impl ClassCtx {
-    pub fn new(namespace: ClassFullname, typarams: Vec<String>) -> ClassCtx {
+    pub fn new(namespace: ClassFullname, typarams: Vec<TyParam>) -> ClassCtx {
         ClassCtx {
             namespace,
             typarams,
@@ -62,12 +66,19 @@ pub struct MethodCtx {
     /// List of inherited ivars
     /// Empty if the method is not `#initialize`
     pub super_ivars: SkIVars, // TODO: this can be just &'a SkIVars
+    /// The module which defines the method, if it is mixed in by `include`
+    pub module: Option<ClassFullname>,
 }
 
 impl MethodCtx {
-    pub fn new(signature: MethodSignature, super_ivars: Option<SkIVars>) -> MethodCtx {
+    pub fn new(
+        signature: MethodSignature,
+        module: Option<ClassFullname>,
+        super_ivars: Option<SkIVars>,
+    ) -> MethodCtx {
         MethodCtx {
             signature,
+            module,
             lvars: Default::default(),
             iivars: Default::default(),
             super_ivars: super_ivars.unwrap_or(Default::default()),
@@ -87,6 +98,10 @@ pub struct LambdaCtx {
     pub captures: Vec<LambdaCapture>,
     /// true if this lambda has `break`
     pub has_break: bool,
+    /// Types of the values given to `next` in this lambda
+    pub next_arg_tys: Vec<TermTy>,
+    /// true if this is the body of `for` (which is expanded into a block)
+    pub in_for: bool,
 }
 
 impl LambdaCtx {
@@ -97,6 +112,8 @@ impl LambdaCtx {
             lvars: Default::default(),
             captures: Default::default(),
             has_break: false,
+            next_arg_tys: vec![],
+            in_for: false,
         }
     }
 }
@@ -113,6 +130,8 @@ pub enum CtxKind {
 /// A local variable
 #[derive(Debug)]
 pub struct CtxLVar {
+    /// Name of the lvar in the generated code (differs from the name in
+    /// the source for the variable of `for`)
     pub name: String,
     pub ty: TermTy,
     pub readonly: bool,
@@ -142,6 +161,8 @@ impl HirMakerContext {
             classes: vec![],
             method: None,
             lambdas: vec![],
+            while_has_break: false,
+            narrowed_lvars: Default::default(),
         }
     }
 
@@ -170,6 +191,18 @@ impl HirMakerContext {
         }
     }
 
+    /// Returns the namespace to resolve constants in. This differs from
+    /// `namespace` in the methods mixed in from a module
+    pub fn const_namespace(&self) -> &str {
+        match &self.method {
+            Some(MethodCtx {
+                module: Some(module),
+                ..
+            }) => &module.0,
+            _ => self.namespace(),
+        }
+    }
+
     /// The type of `self` in the current scope
     pub fn self_ty(&self) -> TermTy {
         match self.current {
@@ -196,7 +229,41 @@ impl HirMakerContext {
 
     /// Add a local variable to current context
     pub fn declare_lvar(&mut self, name: &str, ty: TermTy, readonly: bool) {
-        let lvars = match self.current {
+        let k = name.to_string();
+        let v = CtxLVar {
+            name: name.to_string(),
+            ty,
+            readonly,
+        };
+        self.current_lvars_mut().insert(k, v);
+    }
+
+    /// Declare the variable of a `for` loop, which is stored in the lvar
+    /// `storage_name` and visible only in the loop body.
+    /// Returns the lvar of the same name shadowed by it, if any
+    pub fn push_loop_var(&mut self, name: &str, storage_name: &str, ty: TermTy) -> Option<CtxLVar> {
+        let v = CtxLVar {
+            name: storage_name.to_string(),
+            ty,
+            readonly: true,
+        };
+        self.current_lvars_mut().insert(name.to_string(), v)
+    }
+
+    /// Make the variable of a `for` loop invisible and restore the shadowed one
+    pub fn pop_loop_var(&mut self, name: &str, shadowed: Option<CtxLVar>) {
+        let lvars = self.current_lvars_mut();
+        let v = lvars.remove(name).expect("[BUG] loop var not found");
+        // Keep it with the storage name so that it is allocated
+        lvars.insert(v.name.clone(), v);
+        if let Some(lvar) = shadowed {
+            lvars.insert(name.to_string(), lvar);
+        }
+    }
+
+    /// The local variables of the current scope
+    fn current_lvars_mut(&mut self) -> &mut HashMap<String, CtxLVar> {
+        match self.current {
             CtxKind::Toplevel => &mut self.toplevel.lvars,
             CtxKind::Class => &mut self.classes.last_mut().unwrap().lvars,
             CtxKind::Method => &mut self.method.as_mut().unwrap().lvars,
@@ -212,14 +279,7 @@ impl HirMakerContext {
                     &mut self.toplevel.lvars
                 }
             }
-        };
-        let k = name.to_string();
-        let v = CtxLVar {
-            name: name.to_string(),
-            ty,
-            readonly,
-        };
-        lvars.insert(k, v);
+        }
     }
 
     /// Returns if we're in an `#initialize`
@@ -331,13 +391,13 @@ impl<'a> Iterator for LVarIter<'a> {
 pub fn extract_lvars(lvars: &mut HashMap<String, CtxLVar>) -> HirLVars {
     std::mem::take(lvars)
         .into_iter()
-        .map(|(name, ctx_lvar)| (name, ctx_lvar.ty))
+        .map(|(_, ctx_lvar)| (ctx_lvar.name, ctx_lvar.ty))
         .collect::<Vec<_>>()
 }
 
 impl HirMaker {
     /// Returns type parameter of the current class
-    pub(super) fn current_class_typarams(&self) -> Vec<String> {
+    pub(super) fn current_class_typarams(&self) -> Vec<TyParam> {
         if let Some(class_ctx) = self.ctx.classes.last() {
             if let Some(method_ctx) = &self.ctx.method {
                 if !method_ctx.signature.fullname.is_class_method() {
@@ -349,7 +409,7 @@ impl HirMaker {
     }
 
     /// Returns type parameter of the current method
-    pub(super) fn current_method_typarams(&self) -> Vec<String> {
+    pub(super) fn current_method_typarams(&self) -> Vec<TyParam> {
         if let Some(method_ctx) = &self.ctx.method {
             method_ctx.signature.typarams.clone()
         } else {
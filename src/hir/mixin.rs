@@ -0,0 +1,162 @@
+//! Expands `include Foo` into the instance methods of the module `Foo`.
+//!
+//! Instance methods of a module are not compiled by themselves. Instead, they
+//! are copied into each class which includes the module so that they get the
+//! vtable slots of the class just like the methods defined in the class.
+//! The copied methods remember the module so that the constants in them are
+//! resolved in the namespace of the module, not of the class.
+use crate::ast;
+use crate::error;
+use crate::error::Error;
+use crate::names::*;
+use std::collections::HashMap;
+
+/// Instance methods (and `include`s) of each module
+type Mixins = HashMap<ClassFullname, Vec<ast::Definition>>;
+
+/// Replace `include` in the classes with the instance methods of the modules
+pub fn expand_includes(mut prog: ast::Program) -> Result<ast::Program, Error> {
+    let mut mixins = HashMap::new();
+    for item in &prog.toplevel_items {
+        if let ast::TopLevelItem::Def(def) = item {
+            collect_mixins(&mut mixins, "", def);
+        }
+    }
+    for item in &mut prog.toplevel_items {
+        if let ast::TopLevelItem::Def(def) = item {
+            expand_def(&mixins, "", def)?;
+        }
+    }
+    Ok(prog)
+}
+
+/// Collect the modules defined in `def`
+fn collect_mixins(mixins: &mut Mixins, namespace: &str, def: &ast::Definition) {
+    match def {
+        ast::Definition::ModuleDefinition { name, defs, .. } => {
+            let fullname = name.add_namespace(namespace);
+            defs.iter()
+                .for_each(|d| collect_mixins(mixins, &fullname.0, d));
+            let methods = defs
+                .iter()
+                .filter_map(|d| match d {
+                    ast::Definition::InstanceMethodDefinition {
+                        sig,
+                        body_exprs,
+                        span,
+                        ..
+                    } => Some(ast::Definition::InstanceMethodDefinition {
+                        sig: sig.clone(),
+                        body_exprs: body_exprs.clone(),
+                        span: span.clone(),
+                        // Constants in the method are resolved in the module
+                        module: Some(fullname.clone()),
+                    }),
+                    ast::Definition::IncludeDefinition { .. } => Some(d.clone()),
+                    _ => None,
+                })
+                .collect();
+            mixins.insert(fullname, methods);
+        }
+        ast::Definition::ClassDefinition { name, defs, .. }
+        | ast::Definition::EnumDefinition { name, defs, .. } => {
+            let fullname = name.add_namespace(namespace);
+            defs.iter()
+                .for_each(|d| collect_mixins(mixins, &fullname.0, d));
+        }
+        _ => (),
+    }
+}
+
+/// Expand `include`s in `def` and its inner classes
+fn expand_def(mixins: &Mixins, namespace: &str, def: &mut ast::Definition) -> Result<(), Error> {
+    match def {
+        ast::Definition::ClassDefinition { name, defs, .. }
+        | ast::Definition::EnumDefinition { name, defs, .. } => {
+            let fullname = name.add_namespace(namespace);
+            let mut methods: Vec<ast::Definition> = vec![];
+            for d in defs.iter() {
+                if let ast::Definition::IncludeDefinition {
+                    name: module_name, ..
+                } = d
+                {
+                    let included = mixin_methods(mixins, &fullname.0, module_name, &mut vec![])
+                        .map_err(|e| e.located_at(&d.span()))?;
+                    for m in included {
+                        // Methods of the module included later take precedence
+                        methods.retain(|x| method_name(x) != method_name(&m));
+                        methods.push(m);
+                    }
+                }
+            }
+            defs.retain(|d| match d {
+                ast::Definition::IncludeDefinition { .. } => false,
+                _ => true,
+            });
+            // Methods defined in the class take precedence
+            methods.retain(|m| !defs.iter().any(|d| method_name(d) == method_name(m)));
+            defs.append(&mut methods);
+
+            for d in defs.iter_mut() {
+                expand_def(mixins, &fullname.0, d)?;
+            }
+        }
+        ast::Definition::ModuleDefinition { name, defs, .. } => {
+            // `include` in a module is expanded when the module is included
+            let fullname = name.add_namespace(namespace);
+            for d in defs.iter_mut() {
+                expand_def(mixins, &fullname.0, d)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Returns the instance methods to be mixed in by `include module_name`
+fn mixin_methods(
+    mixins: &Mixins,
+    namespace: &str,
+    module_name: &ConstName,
+    including: &mut Vec<ClassFullname>,
+) -> Result<Vec<ast::Definition>, Error> {
+    let fullname = resolve_module(mixins, namespace, module_name)?;
+    if including.contains(&fullname) {
+        return Err(error::program_error(&format!(
+            "module {} includes itself",
+            fullname
+        )));
+    }
+    including.push(fullname.clone());
+    let mut methods = vec![];
+    for d in &mixins[&fullname] {
+        if let ast::Definition::IncludeDefinition { name, .. } = d {
+            methods.append(&mut mixin_methods(mixins, &fullname.0, name, including)?);
+        } else {
+            methods.push(d.clone());
+        }
+    }
+    including.pop();
+    Ok(methods)
+}
+
+/// Find the module `name` from `namespace` and its outer namespaces
+fn resolve_module(
+    mixins: &Mixins,
+    namespace: &str,
+    name: &ConstName,
+) -> Result<ClassFullname, Error> {
+    let s = name.names.join("::");
+    enclosing_namespaces(namespace)
+        .iter()
+        .map(|ns| class_firstname(&s).add_namespace(ns))
+        .find(|fullname| mixins.contains_key(fullname))
+        .ok_or_else(|| error::name_error(&format!("unknown module: {}", s)))
+}
+
+fn method_name(def: &ast::Definition) -> Option<&MethodFirstname> {
+    match def {
+        ast::Definition::InstanceMethodDefinition { sig, .. } => Some(&sig.name),
+        _ => None,
+    }
+}
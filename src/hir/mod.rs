@@ -1,13 +1,17 @@
 mod accessors;
 pub mod class_dict;
+mod class_objects;
 mod convert_exprs;
 mod hir_maker;
 mod hir_maker_context;
 mod method_dict;
+mod mixin;
 pub mod signature;
 pub mod sk_class;
+mod type_inference;
 use crate::ast;
 use crate::corelib::Corelib;
+use crate::location::LocationSpan;
 pub use crate::hir::class_dict::ClassDict;
 pub use crate::hir::signature::MethodParam;
 pub use crate::hir::signature::MethodSignature;
@@ -118,6 +122,13 @@ impl HirExpressions {
         self.exprs.push(void_const_ref());
         self.ty = ty::raw("Void");
     }
+
+    /// Change the type of `self` to `ty` (which must be a supertype)
+    pub fn bitcast_to(&mut self, ty: TermTy) {
+        let last_expr = self.exprs.pop().unwrap();
+        self.exprs.push(Hir::bit_cast(ty.clone(), last_expr));
+        self.ty = ty;
+    }
 }
 /// Make a HirExpression to refer `::Void`
 fn void_const_ref() -> HirExpression {
@@ -128,6 +139,17 @@ fn void_const_ref() -> HirExpression {
 pub struct HirExpression {
     pub ty: TermTy,
     pub node: HirExpressionBase,
+    pub span: Option<LocationSpan>,
+}
+
+impl HirExpression {
+    /// Returns true if this is an omitted arg (see HirOmittedArg)
+    pub fn is_omitted_arg(&self) -> bool {
+        match self.node {
+            HirExpressionBase::HirOmittedArg => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -152,13 +174,31 @@ pub enum HirExpressionBase {
         cond_expr: Box<HirExpression>,
         body_exprs: Box<HirExpressions>,
     },
+    HirMatchExpression {
+        /// Assigns the value to match into a temporary lvar
+        cond_assign_expr: Box<HirExpression>,
+        clauses: Vec<HirMatchClause>,
+    },
+    HirBeginExpression {
+        body_exprs: Box<HirExpressions>,
+        rescue_clauses: Vec<HirRescueClause>,
+        ensure_exprs: Option<Box<HirExpressions>>,
+    },
     HirBreakExpression {
         from: HirBreakFrom,
     },
+    HirNextExpression {
+        from: HirNextFrom,
+        /// Value of the block (`Void` for `while`)
+        arg: Box<HirExpression>,
+    },
     HirReturnExpression {
         from: HirReturnFrom,
         arg: Box<HirExpression>,
     },
+    HirRaiseExpression {
+        expr: Box<HirExpression>,
+    },
     HirLVarAssign {
         name: String,
         rhs: Box<HirExpression>,
@@ -186,6 +226,15 @@ pub enum HirExpressionBase {
     HirArgRef {
         idx: usize,
     },
+    /// Passed for an omitted arg which has a default value
+    /// (the callee computes the default value)
+    HirOmittedArg,
+    /// The arg if given, otherwise its default value
+    /// (appears at the beginning of a method which has default values)
+    HirArgOrDefault {
+        idx: usize,
+        default_expr: Box<HirExpression>,
+    },
     HirLVarRef {
         name: String,
     },
@@ -239,6 +288,12 @@ pub enum HirExpressionBase {
         cidx: usize,
         rhs: Box<HirExpression>,
     },
+    /// The exception being rescued (only appears in a `rescue` clause)
+    HirCurrentException,
+    /// Evaluate the expressions in order (eg. expanded multiple assignment)
+    HirSequence {
+        exprs: HirExpressions,
+    },
     /// Represents bitcast of an object
     HirBitCast {
         expr: Box<HirExpression>,
@@ -250,6 +305,28 @@ pub enum HirExpressionBase {
         fullname: ClassFullname,
         str_literal_idx: usize,
     },
+    /// Placeholder for an expression which has a compile error.
+    /// Never reaches code_gen because the compilation stops
+    HirErrorExpression,
+}
+
+/// A clause of `match`
+#[derive(Debug)]
+pub struct HirMatchClause {
+    /// Classes of the objects which this clause matches.
+    /// Empty if this clause matches anything (i.e. `else`)
+    pub classes: Vec<ClassFullname>,
+    /// Binds the ivars to lvars and then evaluates the body
+    pub body_exprs: HirExpressions,
+}
+
+/// A clause of `begin`
+#[derive(Debug)]
+pub struct HirRescueClause {
+    /// Classes of the exceptions which this clause rescues
+    pub classes: Vec<ClassFullname>,
+    /// Binds the exception to a lvar (if any) and then evaluates the body
+    pub body_exprs: HirExpressions,
 }
 
 /// Denotes which variable to include in the `captures`
@@ -271,6 +348,13 @@ pub enum HirBreakFrom {
     Block,
 }
 
+/// Denotes what a `next` skips
+#[derive(Debug)]
+pub enum HirNextFrom {
+    While,
+    Block,
+}
+
 /// Denotes what a `return` escapes from
 #[derive(Debug)]
 pub enum HirReturnFrom {
@@ -290,6 +374,7 @@ impl Hir {
             node: HirExpressionBase::HirLogicalNot {
                 expr: Box::new(expr_hir),
             },
+            span: None,
         }
     }
 
@@ -300,6 +385,7 @@ impl Hir {
                 left: Box::new(left_hir),
                 right: Box::new(right_hir),
             },
+            span: None,
         }
     }
 
@@ -310,6 +396,7 @@ impl Hir {
                 left: Box::new(left_hir),
                 right: Box::new(right_hir),
             },
+            span: None,
         }
     }
 
@@ -326,16 +413,54 @@ impl Hir {
                 then_exprs: Box::new(then_hir),
                 else_exprs: Box::new(else_hir),
             },
+            span: None,
         }
     }
 
-    pub fn while_expression(cond_hir: HirExpression, body_hirs: HirExpressions) -> HirExpression {
+    pub fn while_expression(
+        ty: TermTy,
+        cond_hir: HirExpression,
+        body_hirs: HirExpressions,
+    ) -> HirExpression {
         HirExpression {
-            ty: ty::raw("Void"),
+            ty,
             node: HirExpressionBase::HirWhileExpression {
                 cond_expr: Box::new(cond_hir),
                 body_exprs: Box::new(body_hirs),
             },
+            span: None,
+        }
+    }
+
+    pub fn match_expression(
+        ty: TermTy,
+        cond_assign_hir: HirExpression,
+        clauses: Vec<HirMatchClause>,
+    ) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirMatchExpression {
+                cond_assign_expr: Box::new(cond_assign_hir),
+                clauses,
+            },
+            span: None,
+        }
+    }
+
+    pub fn begin_expression(
+        ty: TermTy,
+        body_hirs: HirExpressions,
+        rescue_clauses: Vec<HirRescueClause>,
+        ensure_hirs: Option<HirExpressions>,
+    ) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirBeginExpression {
+                body_exprs: Box::new(body_hirs),
+                rescue_clauses,
+                ensure_exprs: ensure_hirs.map(Box::new),
+            },
+            span: None,
         }
     }
 
@@ -343,6 +468,18 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Never"),
             node: HirExpressionBase::HirBreakExpression { from },
+            span: None,
+        }
+    }
+
+    pub fn next_expression(from: HirNextFrom, arg_expr: HirExpression) -> HirExpression {
+        HirExpression {
+            ty: ty::raw("Never"),
+            node: HirExpressionBase::HirNextExpression {
+                from,
+                arg: Box::new(arg_expr),
+            },
+            span: None,
         }
     }
 
@@ -350,6 +487,17 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Never"),
             node: HirExpressionBase::HirReturnExpression { from, arg: Box::new(arg_expr) },
+            span: None,
+        }
+    }
+
+    pub fn raise_expression(expr: HirExpression) -> HirExpression {
+        HirExpression {
+            ty: ty::raw("Never"),
+            node: HirExpressionBase::HirRaiseExpression {
+                expr: Box::new(expr),
+            },
+            span: None,
         }
     }
 
@@ -360,6 +508,7 @@ impl Hir {
                 name: name.to_string(),
                 rhs: Box::new(rhs),
             },
+            span: None,
         }
     }
 
@@ -379,6 +528,7 @@ impl Hir {
                 writable,
                 self_ty,
             },
+            span: None,
         }
     }
 
@@ -389,6 +539,7 @@ impl Hir {
                 fullname,
                 rhs: Box::new(rhs),
             },
+            span: None,
         }
     }
 
@@ -405,6 +556,7 @@ impl Hir {
                 method_fullname,
                 arg_exprs: arg_hirs,
             },
+            span: None,
         }
     }
 
@@ -419,6 +571,7 @@ impl Hir {
                 lambda_expr: Box::new(varref_expr),
                 arg_exprs: arg_hirs,
             },
+            span: None,
         }
     }
 
@@ -426,6 +579,26 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirArgRef { idx },
+            span: None,
+        }
+    }
+
+    pub fn omitted_arg(ty: TermTy) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirOmittedArg,
+            span: None,
+        }
+    }
+
+    pub fn arg_or_default(ty: TermTy, idx: usize, default_expr: HirExpression) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirArgOrDefault {
+                idx,
+                default_expr: Box::new(default_expr),
+            },
+            span: None,
         }
     }
 
@@ -433,6 +606,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirLVarRef { name },
+            span: None,
         }
     }
 
@@ -440,6 +614,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirIVarRef { name, idx, self_ty },
+            span: None,
         }
     }
 
@@ -447,6 +622,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirConstRef { fullname },
+            span: None,
         }
     }
 
@@ -471,6 +647,7 @@ impl Hir {
                 ret_ty,
                 has_break,
             },
+            span: None,
         }
     }
 
@@ -478,6 +655,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirSelfExpression,
+            span: None,
         }
     }
 
@@ -485,6 +663,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirArrayLiteral { exprs },
+            span: None,
         }
     }
 
@@ -492,6 +671,7 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Float"),
             node: HirExpressionBase::HirFloatLiteral { value },
+            span: None,
         }
     }
 
@@ -499,6 +679,7 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Int"),
             node: HirExpressionBase::HirDecimalLiteral { value },
+            span: None,
         }
     }
 
@@ -506,6 +687,7 @@ impl Hir {
         HirExpression {
             ty: ty::raw("String"),
             node: HirExpressionBase::HirStringLiteral { idx },
+            span: None,
         }
     }
 
@@ -513,6 +695,23 @@ impl Hir {
         HirExpression {
             ty: ty::raw("Bool"),
             node: HirExpressionBase::HirBooleanLiteral { value },
+            span: None,
+        }
+    }
+
+    pub fn current_exception(ty: TermTy) -> HirExpression {
+        HirExpression {
+            ty,
+            node: HirExpressionBase::HirCurrentException,
+            span: None,
+        }
+    }
+
+    pub fn sequence(exprs: HirExpressions) -> HirExpression {
+        HirExpression {
+            ty: exprs.ty.clone(),
+            node: HirExpressionBase::HirSequence { exprs },
+            span: None,
         }
     }
 
@@ -522,6 +721,7 @@ impl Hir {
             node: HirExpressionBase::HirBitCast {
                 expr: Box::new(expr),
             },
+            span: None,
         }
     }
 
@@ -532,6 +732,15 @@ impl Hir {
                 fullname: name.to_class_fullname(),
                 str_literal_idx,
             },
+            span: None,
+        }
+    }
+
+    pub fn error_expression() -> HirExpression {
+        HirExpression {
+            ty: ty::error(),
+            node: HirExpressionBase::HirErrorExpression,
+            span: None,
         }
     }
 
@@ -539,6 +748,7 @@ impl Hir {
         HirExpression {
             ty,
             node: HirExpressionBase::HirLambdaCaptureRef { idx, readonly },
+            span: None,
         }
     }
 
@@ -549,6 +759,7 @@ impl Hir {
                 cidx,
                 rhs: Box::new(rhs),
             },
+            span: None,
         }
     }
 }
@@ -8,7 +8,7 @@ pub struct MethodSignature {
     pub fullname: MethodFullname,
     pub ret_ty: TermTy,
     pub params: Vec<MethodParam>,
-    pub typarams: Vec<String>,
+    pub typarams: Vec<TyParam>,
 }
 
 impl MethodSignature {
@@ -16,6 +16,22 @@ impl MethodSignature {
         &self.fullname.first_name
     }
 
+    /// Number of the params which do not have a default value
+    pub fn required_arity(&self) -> usize {
+        self.params
+            .iter()
+            .take_while(|param| param.default_expr.is_none() && !param.is_rest)
+            .count()
+    }
+
+    /// Return the index of the rest param, if any
+    pub fn rest_param_idx(&self) -> Option<usize> {
+        match self.params.last() {
+            Some(param) if param.is_rest => Some(self.params.len() - 1),
+            _ => None,
+        }
+    }
+
     /// Substitute type parameters with type arguments
     pub fn specialize(&self, class_tyargs: Option<&[TermTy]>, method_tyargs: Option<&[TermTy]>) -> MethodSignature {
         MethodSignature {
@@ -35,6 +51,10 @@ impl MethodSignature {
 pub struct MethodParam {
     pub name: String,
     pub ty: TermTy,
+    /// Evaluated in the method when the argument is omitted
+    pub default_expr: Option<ast::AstExpression>,
+    /// True if this is a rest param (`ty` is `Array<T>`)
+    pub is_rest: bool,
 }
 
 impl MethodParam {
@@ -42,6 +62,20 @@ impl MethodParam {
         MethodParam {
             name: self.name.clone(),
             ty: self.ty.substitute(class_tyargs, method_tyargs),
+            default_expr: self.default_expr.clone(),
+            is_rest: self.is_rest,
+        }
+    }
+
+    /// Return the type of each arg for this param (eg. `Int` for `*a: Int`)
+    pub fn item_ty(&self) -> &TermTy {
+        if self.is_rest {
+            match &self.ty.body {
+                TyBody::TySpe { type_args, .. } => &type_args[0],
+                _ => panic!("[BUG] rest param is not an Array: {:?}", self),
+            }
+        } else {
+            &self.ty
         }
     }
 }
@@ -58,25 +92,74 @@ pub fn find_param<'a>(params: &'a [MethodParam], name: &str) -> Option<(usize, &
 pub fn create_signature(
     class_fullname: &ClassFullname,
     sig: &ast::AstMethodSignature,
-    class_typarams: &[String],
+    class_typarams: &[TyParam],
 ) -> MethodSignature {
     let fullname = method_fullname(class_fullname, &sig.name.0);
-    let ret_ty = convert_typ(&sig.ret_typ, class_typarams, &sig.typarams);
-    let params = convert_params(&sig.params, class_typarams, &sig.typarams);
+    let typarams = convert_method_typarams(&sig.typarams, class_typarams);
+    let ret_ty = convert_typ(&sig.ret_typ, class_typarams, &typarams);
+    let params = convert_params(&sig.params, class_typarams, &typarams);
     MethodSignature {
         fullname,
         ret_ty,
         params,
-        typarams: sig.typarams.clone(),
+        typarams,
     }
 }
 
+/// Create `ty::TyParam`s from `ast::AstTyParam`s of a class.
+/// The upper bounds may refer to the type parameters (eg. `T : Comparable<T>`)
+pub fn convert_typarams(typarams: &[ast::AstTyParam]) -> Vec<TyParam> {
+    let unbounded = _unbounded_typarams(typarams);
+    _convert_typarams(typarams, &unbounded, &[])
+}
+
+/// Create `ty::TyParam`s from `ast::AstTyParam`s of a method.
+/// The upper bounds may refer to the type parameters of the method and
+/// the class
+pub fn convert_method_typarams(
+    typarams: &[ast::AstTyParam],
+    class_typarams: &[TyParam],
+) -> Vec<TyParam> {
+    let unbounded = _unbounded_typarams(typarams);
+    _convert_typarams(typarams, class_typarams, &unbounded)
+}
+
+/// Type parameters of the names without upper bounds (used in the upper
+/// bounds themselves)
+fn _unbounded_typarams(typarams: &[ast::AstTyParam]) -> Vec<TyParam> {
+    typarams
+        .iter()
+        .map(|param| TyParam::new(param.name.clone()))
+        .collect()
+}
+
+fn _convert_typarams(
+    typarams: &[ast::AstTyParam],
+    class_typarams: &[TyParam],
+    method_typarams: &[TyParam],
+) -> Vec<TyParam> {
+    typarams
+        .iter()
+        .map(|param| TyParam {
+            name: param.name.clone(),
+            upper_bound: match &param.upper_bound {
+                Some(typ) => convert_typ(typ, class_typarams, method_typarams),
+                None => ty::raw("Object"),
+            },
+        })
+        .collect()
+}
+
 // TODO: pass the list of visible classes
-fn convert_typ(typ: &ast::Typ, class_typarams: &[String], method_typarams: &[String]) -> TermTy {
-    if let Some(idx) = class_typarams.iter().position(|s| *s == typ.name) {
-        ty::typaram(&typ.name, ty::TyParamKind::Class, idx)
-    } else if let Some(idx) = method_typarams.iter().position(|s| *s == typ.name) {
-        ty::typaram(&typ.name, ty::TyParamKind::Method, idx)
+pub fn convert_typ(
+    typ: &ast::Typ,
+    class_typarams: &[TyParam],
+    method_typarams: &[TyParam],
+) -> TermTy {
+    if let Some(idx) = class_typarams.iter().position(|p| p.name == typ.name) {
+        ty::typaram(&class_typarams[idx], ty::TyParamKind::Class, idx)
+    } else if let Some(idx) = method_typarams.iter().position(|p| p.name == typ.name) {
+        ty::typaram(&method_typarams[idx], ty::TyParamKind::Method, idx)
     } else if typ.typ_args.is_empty() {
         ty::raw(&typ.name)
     } else {
@@ -91,14 +174,21 @@ fn convert_typ(typ: &ast::Typ, class_typarams: &[String], method_typarams: &[Str
 
 pub fn convert_params(
     params: &[ast::Param],
-    class_typarams: &[String],
-    method_typarams: &[String],
+    class_typarams: &[TyParam],
+    method_typarams: &[TyParam],
 ) -> Vec<MethodParam> {
     params
         .iter()
-        .map(|param| MethodParam {
-            name: param.name.to_string(),
-            ty: convert_typ(&param.typ, class_typarams, method_typarams),
+        .map(|param| {
+            let typ = param.typ.as_ref().expect("[BUG] type of param omitted");
+            let ty = convert_typ(typ, class_typarams, method_typarams);
+            MethodParam {
+                name: param.name.to_string(),
+                // eg. `*a: Int` is an `Array<Int>`
+                ty: if param.is_rest { ty::ary(ty) } else { ty },
+                default_expr: param.default_expr.clone(),
+                is_rest: param.is_rest,
+            }
         })
         .collect()
 }
@@ -14,7 +14,15 @@ pub struct SkClass {
     pub ivars: HashMap<String, super::SkIVar>,
     pub method_sigs: HashMap<MethodFirstname, MethodSignature>,
     /// eg. `Void` is an instance, not the class
+    /// (also true for enum cases without fields like `Maybe::None`)
     pub const_is_obj: bool,
+    /// Interfaces declared by this class (eg. `C` for `class A : B, C`,
+    /// `Comparable<A>` for `class A : Comparable<A>`)
+    pub interfaces: Vec<TermTy>,
+    /// true if this is an interface (which has no instances nor metaclass)
+    pub is_interface: bool,
+    /// true if this is an enum (whose instances are always one of the cases)
+    pub is_enum: bool,
 }
 
 impl SkClass {
@@ -22,6 +30,18 @@ impl SkClass {
         self.instance_ty.meta_ty()
     }
 
+    /// Name of the constant which holds the class object of this class
+    pub fn class_object_const(&self) -> ConstFullname {
+        if self.fullname.is_meta() {
+            const_fullname("::Class")
+        } else if self.const_is_obj {
+            // `::Void` holds the instance. Use another name
+            const_fullname(&format!("::{}.class", self.fullname))
+        } else {
+            const_fullname(&format!("::{}", self.fullname))
+        }
+    }
+
     /// List of method names, alphabetic order
     pub fn method_names(&self) -> Vec<MethodFullname> {
         let mut v = self
@@ -59,6 +79,9 @@ impl SkClass {
             ivars: self.ivars.clone(),
             method_sigs,
             const_is_obj: self.const_is_obj,
+            interfaces: self.interfaces.clone(),
+            is_interface: false,
+            is_enum: false,
         }
     }
 }
@@ -0,0 +1,128 @@
+//! Infers the type arguments of a generic method call from the arguments.
+//!
+//! eg. For `ary.map{|x: Int| x.to_s}`, `U` of `Array#map<U>(f: Fn1<T, U>)`
+//! is `String` because the block is a `Fn1<Int, String>`.
+use crate::error;
+use crate::error::Error;
+use crate::hir::*;
+
+/// Return the type arguments of the method type parameters of `sig`.
+/// `sig` must not be specialized with the type arguments of the receiver
+/// (otherwise the type parameters of the caller may be mixed up.)
+pub fn infer_method_tyargs(
+    class_dict: &ClassDict,
+    sig: &MethodSignature,
+    arg_tys: &[&TermTy],
+    splat_ty: Option<&TermTy>,
+) -> Result<Vec<TermTy>, Error> {
+    infer_partially(class_dict, sig, arg_tys, splat_ty)?
+        .into_iter()
+        .zip(sig.typarams.iter())
+        .map(|(tyarg, typaram)| {
+            tyarg.ok_or_else(|| {
+                error::type_error(&format!(
+                    "cannot infer the type argument {} of {} (please specify it like `{}<...>')",
+                    typaram.name, sig.fullname, sig.fullname.first_name
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Similar to `infer_method_tyargs` but returns None for the type
+/// parameters which do not appear in the types of `arg_tys`
+pub fn infer_partially(
+    class_dict: &ClassDict,
+    sig: &MethodSignature,
+    arg_tys: &[&TermTy],
+    splat_ty: Option<&TermTy>,
+) -> Result<Vec<Option<TermTy>>, Error> {
+    let mut tyargs = vec![None; sig.typarams.len()];
+    for (i, arg_ty) in arg_tys.iter().enumerate() {
+        let param = match sig.params.get(i) {
+            Some(param) => param,
+            // The rest of the args are for the rest param
+            None => match sig.params.last() {
+                Some(param) => param,
+                // Arity error (reported later)
+                None => break,
+            },
+        };
+        unify(class_dict, sig, &mut tyargs, param.item_ty(), arg_ty)?;
+    }
+    if let (Some(splat_ty), Some(idx)) = (splat_ty, sig.rest_param_idx()) {
+        unify(class_dict, sig, &mut tyargs, &sig.params[idx].ty, splat_ty)?;
+    }
+    Ok(tyargs)
+}
+
+/// Return true if `ty` contains a method type parameter whose type argument
+/// is not known (i.e. None in `tyargs`)
+pub fn has_unknown_tyarg(ty: &TermTy, tyargs: &[Option<TermTy>]) -> bool {
+    match &ty.body {
+        TyBody::TyParamRef {
+            kind: TyParamKind::Method,
+            idx,
+            ..
+        } => tyargs.get(*idx).map_or(true, |t| t.is_none()),
+        TyBody::TySpe { type_args, .. } => type_args.iter().any(|t| has_unknown_tyarg(t, tyargs)),
+        _ => false,
+    }
+}
+
+/// Find the type arguments by matching `param_ty` with `arg_ty`.
+/// If a type parameter matches two different types, the more general one
+/// is taken. It is an error if neither of them conforms to the other
+fn unify(
+    class_dict: &ClassDict,
+    sig: &MethodSignature,
+    tyargs: &mut [Option<TermTy>],
+    param_ty: &TermTy,
+    arg_ty: &TermTy,
+) -> Result<(), Error> {
+    if arg_ty.is_error_type() {
+        return Ok(());
+    }
+    match (&param_ty.body, &arg_ty.body) {
+        (
+            TyBody::TyParamRef {
+                kind: TyParamKind::Method,
+                idx,
+                name,
+                ..
+            },
+            _,
+        ) => {
+            let new_ty = match &tyargs[*idx] {
+                None => arg_ty.clone(),
+                Some(t) if arg_ty.conforms_to(t, class_dict) => t.clone(),
+                Some(t) if t.conforms_to(arg_ty, class_dict) => arg_ty.clone(),
+                Some(t) => {
+                    return Err(error::type_error(&format!(
+                        "the type argument {} of {} is ambiguous ({} or {})",
+                        name, sig.fullname, t, arg_ty
+                    )))
+                }
+            };
+            tyargs[*idx] = Some(new_ty);
+        }
+        (
+            TyBody::TySpe {
+                base_name,
+                type_args,
+            },
+            _,
+        ) => {
+            // eg. Passing a `Expr::Value<Int>` for `Expr<T>` (`Expr<Int>`),
+            // an `Int` for `Comparable<T>` (`Comparable<Int>`)
+            let ancestor = class_dict.as_ancestor(arg_ty, &class_fullname(base_name));
+            if let Some(TyBody::TySpe { type_args: a2, .. }) = ancestor.as_ref().map(|t| &t.body) {
+                for (p, a) in type_args.iter().zip(a2.iter()) {
+                    unify(class_dict, sig, tyargs, p, a)?;
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
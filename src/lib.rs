@@ -2,8 +2,10 @@
 pub mod ast;
 pub mod code_gen;
 pub mod corelib;
+pub mod diagnostics;
 pub mod error;
 pub mod hir;
+pub mod location;
 pub mod mir;
 pub mod names;
 pub mod parser;
@@ -0,0 +1,35 @@
+//! Locations in the source files (used for error messages)
+use crate::parser::lexer::Cursor;
+use std::rc::Rc;
+
+/// A range in a source file
+#[derive(Debug, PartialEq, Clone)]
+pub struct LocationSpan {
+    /// Path of the source file (empty if not from a file)
+    pub filepath: Rc<String>,
+    pub begin: Cursor,
+    pub end: Cursor,
+}
+
+impl LocationSpan {
+    pub fn new(filepath: &Rc<String>, begin: Cursor, end: Cursor) -> LocationSpan {
+        LocationSpan {
+            filepath: filepath.clone(),
+            begin,
+            end,
+        }
+    }
+}
+
+/// eg. `foo.sk:12:3` (line and column are 1-origin)
+impl std::fmt::Display for LocationSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.filepath,
+            self.begin.line + 1,
+            self.begin.col + 1
+        )
+    }
+}
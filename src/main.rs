@@ -1,3 +1,4 @@
+use shiika::diagnostics;
 use shiika::error::Error;
 use shiika::runner;
 #[macro_use]
@@ -41,15 +42,8 @@ fn run(filepath: &str) {
 }
 
 fn print_err(err: Error) {
-    println!("{:?}: {}", err.details, err.msg);
-    for frame in err.backtrace.frames() {
-        for symbol in frame.symbols() {
-            if let Some(name) = symbol.name() {
-                let s = format!("{}", name);
-                if s.starts_with("shiika") {
-                    println!("- {}", s);
-                }
-            }
-        }
+    print!("{}", diagnostics::render(&err));
+    if diagnostics::show_backtrace(&err) {
+        print!("{}", diagnostics::render_backtrace(&err));
     }
 }
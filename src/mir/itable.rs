@@ -0,0 +1,140 @@
+use crate::hir::sk_class::SkClass;
+use crate::mir::vtable::VTables;
+use crate::names::*;
+use std::collections::HashMap;
+
+/// Method table of an interface for a class which conforms to it
+#[derive(Debug)]
+pub struct ITable {
+    /// The interface
+    pub iface_fullname: ClassFullname,
+    /// Implementations of the methods of the interface, ordered by index
+    pub fullnames: Vec<MethodFullname>,
+}
+
+/// Method tables for calling the methods of interfaces.
+///
+/// The vtable index of a method differs between the classes which conform
+/// to an interface. So a method of an interface is called via the table of
+/// the interface for the class of the receiver.
+/// Each vtable has a reference to the list of the itables of the class
+/// (indexed by the interface) so the table is found in constant time.
+#[derive(Debug)]
+pub struct ITables {
+    /// All the interfaces, sorted by name. The position is the index of
+    /// the interface in the list of the itables of a class
+    ifaces: Vec<ClassFullname>,
+    /// Tables of each class which conforms to any interface
+    /// (`None` for the interfaces the class does not conform to)
+    contents: HashMap<ClassFullname, Vec<Option<ITable>>>,
+    /// Interface and index of each method of the interfaces
+    index: HashMap<MethodFullname, (usize, usize)>,
+    /// Number of the methods of each interface
+    sizes: Vec<usize>,
+}
+
+impl ITables {
+    pub fn build(sk_classes: &HashMap<ClassFullname, SkClass>, vtables: &VTables) -> ITables {
+        let mut ifaces = sk_classes
+            .values()
+            .filter(|cls| cls.is_interface)
+            .collect::<Vec<_>>();
+        // Sort to make the output stable
+        ifaces.sort_unstable_by(|a, b| a.fullname.0.cmp(&b.fullname.0));
+        let mut index = HashMap::new();
+        let mut sizes = vec![];
+        let mut iface_names = vec![];
+        for (i, iface) in ifaces.iter().enumerate() {
+            let names = iface.method_names();
+            for (j, name) in names.iter().enumerate() {
+                index.insert(name.clone(), (i, j));
+            }
+            sizes.push(names.len());
+            iface_names.push(iface.fullname.clone());
+        }
+
+        let mut contents = HashMap::new();
+        let classes = sk_classes
+            .values()
+            .filter(|cls| !cls.is_interface && !cls.fullname.is_meta());
+        for cls in classes {
+            let itables = ifaces
+                .iter()
+                .map(|iface| {
+                    if !conforms(sk_classes, cls, &iface.fullname) {
+                        return None;
+                    }
+                    let vtable = vtables
+                        .get(&cls.fullname)
+                        .unwrap_or_else(|| panic!("[BUG] vtable of {} not found", cls.fullname));
+                    let fullnames = iface
+                        .method_names()
+                        .iter()
+                        .map(|name| {
+                            vtable
+                                .resolve(&name.first_name)
+                                .cloned()
+                                .unwrap_or_else(|| {
+                                    panic!("[BUG] {} does not have {}", cls.fullname, name)
+                                })
+                        })
+                        .collect();
+                    Some(ITable {
+                        iface_fullname: iface.fullname.clone(),
+                        fullnames,
+                    })
+                })
+                .collect::<Vec<_>>();
+            if itables.iter().any(|t| t.is_some()) {
+                contents.insert(cls.fullname.clone(), itables);
+            }
+        }
+        ITables {
+            ifaces: iface_names,
+            contents,
+            index,
+            sizes,
+        }
+    }
+
+    /// If the method belongs to an interface, return the index of the
+    /// interface, the index of the method and the size of the table
+    pub fn method_idx(&self, method_fullname: &MethodFullname) -> Option<(usize, usize, usize)> {
+        self.index
+            .get(method_fullname)
+            .map(|(i, j)| (*i, *j, self.sizes[*i]))
+    }
+
+    /// Return the number of the interfaces
+    pub fn n_interfaces(&self) -> usize {
+        self.ifaces.len()
+    }
+
+    /// Return the tables of a class, if it conforms to any interface
+    pub fn get(&self, classname: &ClassFullname) -> Option<&[Option<ITable>]> {
+        self.contents.get(classname).map(|v| &v[..])
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, ClassFullname, Vec<Option<ITable>>> {
+        self.contents.iter()
+    }
+}
+
+/// Return true if the class or one of its ancestors declares the interface
+fn conforms(
+    sk_classes: &HashMap<ClassFullname, SkClass>,
+    class: &SkClass,
+    iface: &ClassFullname,
+) -> bool {
+    let mut cls = Some(class);
+    while let Some(c) = cls {
+        if c.interfaces.iter().any(|t| &t.vtable_name() == iface) {
+            return true;
+        }
+        cls = c
+            .superclass_fullname
+            .as_ref()
+            .and_then(|name| sk_classes.get(name));
+    }
+    false
+}
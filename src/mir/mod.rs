@@ -1,14 +1,22 @@
+pub mod itable;
 pub mod vtable;
 use crate::hir::Hir;
+pub use crate::mir::itable::ITables;
 pub use crate::mir::vtable::VTables;
 
 #[derive(Debug)]
 pub struct Mir {
     pub hir: Hir,
     pub vtables: VTables,
+    pub itables: ITables,
 }
 
 pub fn build(hir: Hir) -> Mir {
     let vtables = VTables::build(&hir.sk_classes);
-    Mir { hir, vtables }
+    let itables = ITables::build(&hir.sk_classes, &vtables);
+    Mir {
+        hir,
+        vtables,
+        itables,
+    }
 }
@@ -25,6 +25,11 @@ impl VTable {
     /// Build a VTable of a class
     pub fn build(super_vtable: &VTable, class: &SkClass) -> VTable {
         let mut vtable = super_vtable.clone();
+        // Methods of an interface are called via `ITables`. Only the methods
+        // of `Object` are called via the vtable
+        if class.is_interface {
+            return vtable;
+        }
         for name in class.method_names() {
             if vtable.contains(&name.first_name) {
                 vtable.update(name);
@@ -51,6 +56,11 @@ impl VTable {
         self.fullnames.push(name);
     }
 
+    /// Returns the full name of the method which is called by `name`
+    pub fn resolve(&self, name: &MethodFirstname) -> Option<&MethodFullname> {
+        self.index.get(name).map(|i| &self.fullnames[*i])
+    }
+
     /// Returns the size
     fn size(&self) -> usize {
         self.fullnames.len()
@@ -113,6 +123,11 @@ impl VTables {
         (vtable.get(&method_name), vtable.size())
     }
 
+    /// Return the vtable of a class
+    pub fn get(&self, classname: &ClassFullname) -> Option<&VTable> {
+        self.contents.get(classname)
+    }
+
     // REFACTOR: it's better to implement Iterator (I just don't know how to)
     pub fn iter(&self) -> std::collections::hash_map::Iter<'_, ClassFullname, VTable> {
         self.contents.iter()
@@ -18,6 +18,22 @@ pub fn class_firstname(s: &str) -> ClassFirstname {
     ClassFirstname(s.to_string())
 }
 
+/// Returns `namespace` and its outer namespaces, innermost first
+/// eg. `["A::B", "A", ""]` for `"A::B"`
+pub fn enclosing_namespaces(namespace: &str) -> Vec<String> {
+    let mut v = vec![];
+    let mut names = namespace
+        .split("::")
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    while !names.is_empty() {
+        v.push(names.join("::"));
+        names.pop();
+    }
+    v.push("".to_string());
+    v
+}
+
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct ClassFullname(pub String);
 
@@ -196,13 +212,13 @@ impl ConstName {
 
     /// Returns the instance type when this const refers to a class
     /// eg. "Object" -> `TermTy(Object)`
-    pub fn to_ty(&self, class_typarams: &[String], method_typarams: &[String]) -> TermTy {
+    pub fn to_ty(&self, class_typarams: &[TyParam], method_typarams: &[TyParam]) -> TermTy {
         if self.args.is_empty() {
             let s = self.names.join("::");
-            if let Some(i) = class_typarams.iter().position(|name| *name == s) {
-                ty::typaram(s, ty::TyParamKind::Class, i)
-            } else if let Some(i) = method_typarams.iter().position(|name| *name == s) {
-                ty::typaram(s, ty::TyParamKind::Method, i)
+            if let Some(i) = class_typarams.iter().position(|p| p.name == s) {
+                ty::typaram(&class_typarams[i], ty::TyParamKind::Class, i)
+            } else if let Some(i) = method_typarams.iter().position(|p| p.name == s) {
+                ty::typaram(&method_typarams[i], ty::TyParamKind::Method, i)
             } else {
                 ty::raw(&self.names.join("::"))
             }
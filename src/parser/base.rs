@@ -2,6 +2,7 @@
 pub use crate::ast;
 pub use crate::ast::*;
 pub use crate::error::*;
+pub use crate::location::LocationSpan;
 pub use crate::parser::lexer;
 pub use crate::parser::lexer::*;
 pub use crate::parser::token::Token;
@@ -21,6 +22,35 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    /// Record `err` and skip tokens to resume parsing.
+    ///
+    /// Skips to the next line which is not indented deeper than `begin`
+    /// (the beginning of the erroneous construct). If the line is an `end`
+    /// of the same indentation, it is skipped too because it should close
+    /// the erroneous construct.
+    pub(super) fn recover(&mut self, err: Error, begin: &Cursor) {
+        self.errors.push(err);
+        self.set_lexer_gtgt_mode(false);
+        loop {
+            match self.current_token() {
+                Token::Eof => return,
+                Token::Separator => {
+                    self.skip_wsn();
+                    let cur = self.current_position();
+                    if cur.line > begin.line && cur.col <= begin.col {
+                        if cur.col == begin.col && self.current_token_is(Token::KwEnd) {
+                            self.consume_token();
+                        }
+                        return;
+                    }
+                }
+                _ => {
+                    self.consume_token();
+                }
+            }
+        }
+    }
+
     /// Generates error if the current token does not equal to `token`.
     /// Consumes the token if succeed.
     ///
@@ -123,10 +153,31 @@ impl<'a> Parser<'a> {
             msg: msg.to_string(),
             backtrace: backtrace::Backtrace::new(),
             details: ErrorDetails::ParseError {
+                filepath: self.filepath.to_string(),
                 location: self.lexer.cur.clone(),
             },
             source: None,
+            span: self.span_from(self.lexer.cur.clone()),
+            notes: vec![],
+        }
+    }
+
+    /// Returns the span from `begin` to the current position
+    pub(super) fn span_from(&self, begin: Cursor) -> Option<LocationSpan> {
+        Some(LocationSpan::new(
+            &self.filepath,
+            begin,
+            self.lexer.cur.clone(),
+        ))
+    }
+
+    /// Set the span of `expr` unless it is already set
+    /// (i.e. `expr` is created by an inner parsing function)
+    pub(super) fn set_span(&self, mut expr: AstExpression, begin: Cursor) -> AstExpression {
+        if expr.span.is_none() {
+            expr.span = self.span_from(begin);
         }
+        expr
     }
 
     /// Print parser debug log (uncomment to enable)
@@ -2,12 +2,18 @@ use super::super::ast;
 use super::base::*;
 use super::Parser; // REFACTOR: use crate:: instead of super
 use crate::names::*;
+use crate::ty;
 
 impl<'a> Parser<'a> {
     pub fn parse_definitions(&mut self) -> Result<Vec<ast::Definition>, Error> {
         let mut defs = vec![];
-        while let Some(def) = self.parse_definition()? {
-            defs.push(def);
+        loop {
+            let begin = self.current_position();
+            match self.parse_definition() {
+                Ok(Some(def)) => defs.push(def),
+                Ok(None) => break,
+                Err(e) => self.recover(e, &begin),
+            }
             self.skip_wsn();
         }
         Ok(defs)
@@ -16,6 +22,10 @@ impl<'a> Parser<'a> {
     fn parse_definition(&mut self) -> Result<Option<ast::Definition>, Error> {
         match self.current_token() {
             Token::KwClass => Ok(Some(self.parse_class_definition()?)),
+            Token::KwEnum => Ok(Some(self.parse_enum_definition()?)),
+            Token::KwModule => Ok(Some(self.parse_module_definition()?)),
+            Token::KwInterface => Ok(Some(self.parse_interface_definition()?)),
+            Token::KwInclude => Ok(Some(self.parse_include_definition()?)),
             Token::KwDef => Ok(Some(self.parse_method_definition()?)),
             Token::UpperWord(_) => Ok(Some(self.parse_const_definition()?)),
             _ => Ok(None),
@@ -25,6 +35,7 @@ impl<'a> Parser<'a> {
     pub fn parse_class_definition(&mut self) -> Result<ast::Definition, Error> {
         self.debug_log("parse_class_definition");
         self.lv += 1;
+        let begin = self.current_position();
         let name;
         let defs;
 
@@ -54,31 +65,276 @@ impl<'a> Parser<'a> {
             vec![]
         };
 
-        // Superclass name (optional)
+        // Superclass name and interfaces (optional)
         let mut super_name = class_fullname("Object");
+        let mut interfaces = vec![];
         self.skip_ws();
         if self.current_token_is(Token::Colon) {
             self.consume_token();
             self.skip_wsn();
+            let typ = self.parse_typ()?;
+            if typ.typ_args.is_empty() {
+                super_name = class_fullname(&typ.name);
+            } else {
+                // eg. `class A : Comparable<A>` (superclasses cannot have
+                // type arguments)
+                interfaces.push(typ);
+            }
+            self.skip_ws();
+            while self.consume(Token::Comma) {
+                self.skip_wsn();
+                interfaces.push(self.parse_typ()?);
+                self.skip_ws();
+            }
+        }
+
+        self.expect_sep()?;
+
+        // Internal definitions
+        defs = self.parse_definitions()?;
+
+        // `end'
+        match self.current_token() {
+            Token::KwEnd => {
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "missing `end' for class {:?}; got {:?}",
+                    name,
+                    token
+                ))
+            }
+        }
+
+        self.lv -= 1;
+        Ok(ast::Definition::ClassDefinition {
+            name,
+            typarams,
+            super_name,
+            interfaces,
+            defs,
+            span: self.span_from(begin),
+        })
+    }
+
+    pub fn parse_module_definition(&mut self) -> Result<ast::Definition, Error> {
+        self.debug_log("parse_module_definition");
+        self.lv += 1;
+        let begin = self.current_position();
+        let name;
+
+        // `module'
+        assert!(self.consume(Token::KwModule));
+        self.skip_ws();
+
+        // Module name
+        match self.current_token() {
+            Token::UpperWord(s) => {
+                name = class_firstname(s);
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "module name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        }
+        self.skip_ws();
+        self.expect_sep()?;
+
+        // Internal definitions
+        let defs = self.parse_definitions()?;
+
+        // `end'
+        match self.current_token() {
+            Token::KwEnd => {
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "missing `end' for module {:?}; got {:?}",
+                    name,
+                    token
+                ))
+            }
+        }
+
+        self.lv -= 1;
+        Ok(ast::Definition::ModuleDefinition {
+            name,
+            defs,
+            span: self.span_from(begin),
+        })
+    }
+
+    pub fn parse_interface_definition(&mut self) -> Result<ast::Definition, Error> {
+        self.debug_log("parse_interface_definition");
+        self.lv += 1;
+        let begin = self.current_position();
+        let name;
+        let mut sigs = vec![];
+
+        // `interface'
+        assert!(self.consume(Token::KwInterface));
+        self.skip_ws();
+
+        // Interface name
+        match self.current_token() {
+            Token::UpperWord(s) => {
+                name = class_firstname(s);
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "interface name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        }
+
+        // Type parameters (optional)
+        let typarams = if self.current_token_is(Token::LessThan) {
+            self.parse_typarams()?
+        } else {
+            vec![]
+        };
+        self.skip_ws();
+        self.expect_sep()?;
+
+        // Method signatures (`def foo(bar) -> Baz` without body and `end')
+        while self.current_token_is(Token::KwDef) {
+            self.set_lexer_state(LexerState::MethodName);
+            self.consume_token();
+            self.skip_ws();
+            let (sig, is_class_method) = self.parse_method_signature()?;
+            if is_class_method {
+                return Err(parse_error!(
+                    self,
+                    "interface cannot have class methods ({:?})",
+                    sig.name
+                ));
+            }
+            sigs.push(sig);
+            self.skip_ws();
+            self.expect_sep()?;
+        }
+
+        // `end'
+        match self.current_token() {
+            Token::KwEnd => {
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "missing `end' for interface {:?}; got {:?}",
+                    name,
+                    token
+                ))
+            }
+        }
+
+        self.lv -= 1;
+        Ok(ast::Definition::InterfaceDefinition {
+            name,
+            typarams,
+            sigs,
+            span: self.span_from(begin),
+        })
+    }
+
+    // Parse `include Foo::Bar`
+    fn parse_include_definition(&mut self) -> Result<ast::Definition, Error> {
+        self.debug_log("parse_include_definition");
+        self.lv += 1;
+        let begin = self.current_position();
+
+        // `include'
+        assert!(self.consume(Token::KwInclude));
+        self.skip_ws();
+        let name = self.parse_const_path("module name")?;
+        let span = self.span_from(begin);
+        self.skip_ws();
+        self.expect_sep()?;
+
+        self.lv -= 1;
+        Ok(ast::Definition::IncludeDefinition { name, span })
+    }
+
+    // Parse `Foo::Bar` (without type arguments)
+    fn parse_const_path(&mut self, what: &str) -> Result<ConstName, Error> {
+        let mut names = vec![];
+        loop {
             match self.current_token() {
                 Token::UpperWord(s) => {
-                    super_name = class_fullname(s);
+                    names.push(s.to_string());
                     self.consume_token();
                 }
                 token => {
                     return Err(parse_error!(
                         self,
-                        "superclass name must start with A-Z but got {:?}",
+                        "{} must start with A-Z but got {:?}",
+                        what,
                         token
                     ))
                 }
             }
+            if !self.consume(Token::ColonColon) {
+                break;
+            }
+        }
+        Ok(const_name(names))
+    }
+
+    pub fn parse_enum_definition(&mut self) -> Result<ast::Definition, Error> {
+        self.debug_log("parse_enum_definition");
+        self.lv += 1;
+        let begin = self.current_position();
+        let name;
+        let mut cases = vec![];
+
+        // `enum'
+        assert!(self.consume(Token::KwEnum));
+        self.skip_ws();
+
+        // Enum name
+        match self.current_token() {
+            Token::UpperWord(s) => {
+                name = class_firstname(s);
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "enum name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
         }
 
+        // Type parameters (optional)
+        let typarams = if self.current_token_is(Token::LessThan) {
+            self.parse_typarams()?
+        } else {
+            vec![]
+        };
+        self.skip_ws();
         self.expect_sep()?;
 
+        // Enum cases
+        while self.current_token_is(Token::KwCase) {
+            cases.push(self.parse_enum_case()?);
+            self.skip_wsn();
+        }
+
         // Internal definitions
-        defs = self.parse_definitions()?;
+        let defs = self.parse_definitions()?;
 
         // `end'
         match self.current_token() {
@@ -88,7 +344,7 @@ impl<'a> Parser<'a> {
             token => {
                 return Err(parse_error!(
                     self,
-                    "missing `end' for class {:?}; got {:?}",
+                    "missing `end' for enum {:?}; got {:?}",
                     name,
                     token
                 ))
@@ -96,17 +352,58 @@ impl<'a> Parser<'a> {
         }
 
         self.lv -= 1;
-        Ok(ast::Definition::ClassDefinition {
+        Ok(ast::Definition::EnumDefinition {
             name,
             typarams,
-            super_name,
+            cases,
             defs,
+            span: self.span_from(begin),
         })
     }
 
+    // Parse `case Foo(a: Int, b: String)`
+    fn parse_enum_case(&mut self) -> Result<ast::EnumCase, Error> {
+        self.debug_log("parse_enum_case");
+        self.lv += 1;
+        let name;
+
+        // `case'
+        assert!(self.consume(Token::KwCase));
+        self.skip_ws();
+
+        // Case name
+        match self.current_token() {
+            Token::UpperWord(s) => {
+                name = class_firstname(s);
+                self.consume_token();
+            }
+            token => {
+                return Err(parse_error!(
+                    self,
+                    "enum case name must start with A-Z but got {:?}",
+                    token
+                ))
+            }
+        }
+
+        // Params (optional)
+        let params = if self.consume(Token::LParen) {
+            self.skip_wsn();
+            self.parse_params(false, false, vec![Token::RParen])?
+        } else {
+            vec![]
+        };
+        self.skip_ws();
+        self.expect_sep()?;
+
+        self.lv -= 1;
+        Ok(ast::EnumCase { name, params })
+    }
+
     pub fn parse_method_definition(&mut self) -> Result<ast::Definition, Error> {
         self.debug_log("parse_method_definition");
         self.lv += 1;
+        let begin = self.current_position();
         // `def'
         self.set_lexer_state(LexerState::MethodName);
         assert!(self.consume(Token::KwDef));
@@ -119,7 +416,15 @@ impl<'a> Parser<'a> {
 
         // Body (optional)
         let mut body_exprs = iparam_exprs(&sig.params);
-        body_exprs.append(&mut self.parse_exprs(vec![Token::KwEnd])?);
+        let body_begin = self.current_position();
+        let mut exprs = self.parse_exprs(vec![Token::KwEnd, Token::KwRescue, Token::KwEnsure])?;
+        if self.current_token_is(Token::KwEnd) {
+            body_exprs.append(&mut exprs);
+        } else {
+            // `def foo ... rescue ... end` is the same as `def foo begin ... rescue ... end end`
+            let expr = self.parse_rescue_and_ensure(exprs)?;
+            body_exprs.push(self.set_span(expr, body_begin));
+        }
 
         // `end'
         self.skip_wsn();
@@ -138,10 +443,20 @@ impl<'a> Parser<'a> {
         }
 
         self.lv -= 1;
+        let span = self.span_from(begin);
         if is_class_method {
-            Ok(ast::Definition::ClassMethodDefinition { sig, body_exprs })
+            Ok(ast::Definition::ClassMethodDefinition {
+                sig,
+                body_exprs,
+                span,
+            })
         } else {
-            Ok(ast::Definition::InstanceMethodDefinition { sig, body_exprs })
+            Ok(ast::Definition::InstanceMethodDefinition {
+                sig,
+                body_exprs,
+                span,
+                module: None,
+            })
         }
     }
 
@@ -184,7 +499,7 @@ impl<'a> Parser<'a> {
                 self.skip_wsn();
                 let is_initialize =
                     !is_class_method && name == Some(method_firstname("initialize"));
-                params = self.parse_params(is_initialize, vec![Token::RParen])?;
+                params = self.parse_params(is_initialize, false, vec![Token::RParen])?;
             }
             // Has no params
             _ => {
@@ -249,7 +564,8 @@ impl<'a> Parser<'a> {
     // Parse type parameters of a class or a method
     // - `class Foo<A, B, C>`
     // - `def foo<A, B, C>( ... )`
-    fn parse_typarams(&mut self) -> Result<Vec<String>, Error> {
+    // - `class SortedSet<T : Comparable>` (with an upper bound)
+    fn parse_typarams(&mut self) -> Result<Vec<ast::AstTyParam>, Error> {
         let mut typarams = vec![];
         debug_assert!(self.consume(Token::LessThan));
         self.skip_wsn();
@@ -260,9 +576,18 @@ impl<'a> Parser<'a> {
                     break;
                 }
                 Token::UpperWord(s) => {
-                    typarams.push(s.to_string());
+                    let name = s.to_string();
                     self.consume_token();
                     self.skip_wsn();
+                    let upper_bound = if self.consume(Token::Colon) {
+                        self.skip_wsn();
+                        let typ = self.parse_typ()?;
+                        self.skip_wsn();
+                        Some(typ)
+                    } else {
+                        None
+                    };
+                    typarams.push(ast::AstTyParam { name, upper_bound });
                 }
                 Token::Comma => {
                     self.consume_token();
@@ -282,9 +607,12 @@ impl<'a> Parser<'a> {
 
     // Parse parameters
     // - The `(` should be consumed beforehand
+    // - `for_lambda` is true for lambdas and blocks (they cannot have
+    //   default values or rest params)
     pub(super) fn parse_params(
         &mut self,
         is_initialize: bool,
+        for_lambda: bool,
         stop_toks: Vec<Token>,
     ) -> Result<Vec<ast::Param>, Error> {
         let mut params = vec![];
@@ -294,12 +622,13 @@ impl<'a> Parser<'a> {
                 match self.current_token() {
                     Token::IVar(_) => {
                         if is_initialize {
-                            params.push(self.parse_param()?);
+                            params.push(self.parse_param(for_lambda)?);
                         } else {
                             return Err(parse_error!(self, "@ is only used in `initialize'"));
                         }
                     }
-                    Token::LowerWord(_) => params.push(self.parse_param()?),
+                    Token::LowerWord(_) | Token::Mul => params.push(self.parse_param(for_lambda)?),
+                    Token::LParen if for_lambda => params.push(self.parse_param(for_lambda)?),
                     token => {
                         return Err(parse_error!(
                             self,
@@ -308,6 +637,23 @@ impl<'a> Parser<'a> {
                         ))
                     }
                 }
+                if let [.., prev, last] = params.as_slice() {
+                    if prev.is_rest {
+                        return Err(parse_error!(
+                            self,
+                            "rest param `{}' must be the last one",
+                            prev.name
+                        ));
+                    }
+                    if prev.default_expr.is_some() && last.default_expr.is_none() && !last.is_rest {
+                        return Err(parse_error!(
+                            self,
+                            "param `{}' needs a default value because `{}' has one",
+                            last.name,
+                            prev.name
+                        ));
+                    }
+                }
                 self.skip_wsn();
             }
             // Next param or exit
@@ -332,9 +678,16 @@ impl<'a> Parser<'a> {
         Ok(params)
     }
 
-    fn parse_param(&mut self) -> Result<ast::Param, Error> {
+    fn parse_param(&mut self, for_lambda: bool) -> Result<ast::Param, Error> {
         let name;
         let is_iparam;
+        let mut destructure = None;
+
+        // `*' (optional)
+        let is_rest = self.consume(Token::Mul);
+        if is_rest && for_lambda {
+            return Err(parse_error!(self, "rest param is not allowed here"));
+        }
 
         // Name
         match self.current_token() {
@@ -343,11 +696,18 @@ impl<'a> Parser<'a> {
                 self.consume_token();
                 is_iparam = false;
             }
-            Token::IVar(s) => {
+            Token::IVar(s) if !is_rest => {
                 name = s.to_string();
                 self.consume_token();
                 is_iparam = true;
             }
+            Token::LParen if for_lambda => {
+                let names = self.parse_destructuring_param_names()?;
+                // Not a valid identifier, so that it does not conflict with other variables
+                name = format!("({})", names.join(", "));
+                destructure = Some(names);
+                is_iparam = false;
+            }
             token => {
                 return Err(parse_error!(
                     self,
@@ -358,20 +718,73 @@ impl<'a> Parser<'a> {
         }
         self.skip_ws();
 
-        // `:'
-        self.expect(Token::Colon)?;
-        self.skip_ws();
+        // Type (may be omitted for lambdas, eg. `|x|`)
+        let typ = if for_lambda && !self.current_token_is(Token::Colon) {
+            None
+        } else {
+            // `:'
+            self.expect(Token::Colon)?;
+            self.skip_ws();
+            let typ = self.parse_typ()?;
+            self.skip_ws();
+            Some(typ)
+        };
 
-        // Type
-        let typ = self.parse_typ()?;
+        // Default value (optional)
+        let default_expr = if self.consume(Token::Equal) {
+            if for_lambda || is_rest {
+                return Err(parse_error!(self, "default value is not allowed here"));
+            }
+            self.skip_wsn();
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
 
         Ok(ast::Param {
             name,
             typ,
             is_iparam,
+            is_rest,
+            default_expr,
+            destructure,
         })
     }
 
+    /// Parse `(k, v)` of `|(k, v): (K, V)|`. Returns `["k", "v"]`
+    fn parse_destructuring_param_names(&mut self) -> Result<Vec<String>, Error> {
+        assert!(self.consume(Token::LParen));
+        let mut names = vec![];
+        loop {
+            self.skip_ws();
+            match self.current_token() {
+                Token::LowerWord(s) => {
+                    names.push(s.to_string());
+                    self.consume_token();
+                }
+                token => {
+                    return Err(parse_error!(
+                        self,
+                        "invalid token in destructuring param: {:?}",
+                        token
+                    ))
+                }
+            }
+            self.skip_ws();
+            if self.consume(Token::RParen) {
+                break;
+            }
+            self.expect(Token::Comma)?;
+        }
+        if names.len() < 2 {
+            return Err(parse_error!(
+                self,
+                "destructuring param needs two or more names"
+            ));
+        }
+        Ok(names)
+    }
+
     fn parse_typ(&mut self) -> Result<ast::Typ, Error> {
         let mut name = String::new();
         loop {
@@ -391,6 +804,11 @@ impl<'a> Parser<'a> {
                     self.set_lexer_gtgt_mode(false);
                     return Ok(ast::Typ { name, typ_args });
                 }
+                // Tuple type (eg. `(Int, Bool)`)
+                Token::LParen if name.is_empty() => {
+                    self.consume_token();
+                    return self.parse_tuple_typ();
+                }
                 token => {
                     if name.is_empty() {
                         return Err(parse_error!(self, "invalid token as type: {:?}", token));
@@ -405,6 +823,46 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parse `Int, Bool)` of `(Int, Bool)` (which is `Tuple2<Int, Bool>`)
+    fn parse_tuple_typ(&mut self) -> Result<ast::Typ, Error> {
+        let mut typ_args = vec![];
+        loop {
+            self.skip_wsn();
+            typ_args.push(self.parse_typ()?);
+            self.skip_wsn();
+            match self.current_token() {
+                Token::Comma => {
+                    self.consume_token();
+                }
+                Token::RParen => {
+                    self.consume_token();
+                    break;
+                }
+                token => {
+                    return Err(parse_error!(
+                        self,
+                        "invalid token in tuple type: {:?}",
+                        token
+                    ))
+                }
+            }
+        }
+        if typ_args.len() < 2 {
+            return Err(parse_error!(self, "tuple type needs two or more elements"));
+        }
+        if typ_args.len() > ty::MAX_TUPLE_SIZE {
+            return Err(parse_error!(
+                self,
+                "tuple with more than {} elements is not supported",
+                ty::MAX_TUPLE_SIZE
+            ));
+        }
+        Ok(ast::Typ {
+            name: ty::tuple_class_name(typ_args.len()),
+            typ_args,
+        })
+    }
+
     fn parse_typ_args(&mut self) -> Result<Vec<ast::Typ>, Error> {
         let mut typ_args = vec![];
         loop {
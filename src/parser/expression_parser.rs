@@ -1,4 +1,4 @@
-use crate::names::ConstName;
+use crate::names::{const_name, ConstName};
 use crate::parser::base::*;
 use std::collections::HashMap;
 
@@ -26,7 +26,9 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse_expr(&mut self) -> Result<AstExpression, Error> {
-        self.parse_var_decl()
+        let begin = self.current_position();
+        let expr = self.parse_var_decl()?;
+        Ok(self.set_span(expr, begin))
     }
 
     pub fn parse_var_decl(&mut self) -> Result<AstExpression, Error> {
@@ -70,6 +72,11 @@ impl<'a> Parser<'a> {
         self.lv += 1;
         self.debug_log("parse_if_unless_modifier");
         let mut expr = self.parse_call_wo_paren()?;
+        if self.next_nonspace_token() == Token::Comma {
+            if let Some(masgn) = self._try_parse_multiple_assignment(&expr)? {
+                expr = masgn;
+            }
+        }
         if self.next_nonspace_token() == Token::ModIf {
             self.skip_ws();
             assert!(self.consume(Token::ModIf));
@@ -94,14 +101,15 @@ impl<'a> Parser<'a> {
     fn parse_call_wo_paren(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_call_wo_paren");
+        let begin = self.current_position();
 
         // If `LowerWord + Space`, see if the rest is an argument list
         match &self.current_token() {
-            Token::LowerWord(_) | Token::KwReturn => {
+            Token::LowerWord(_) | Token::KwReturn | Token::KwNext | Token::KwRaise => {
                 if self.peek_next_token() == Token::Space {
                     if let Some(expr) = self._try_parse_call_wo_paren()? {
                         self.lv -= 1;
-                        return Ok(expr)
+                        return Ok(self.set_span(expr, begin));
                     }
                 }
             }
@@ -113,7 +121,7 @@ impl<'a> Parser<'a> {
 
         // See if it is a method invocation (eg. `x.foo 1, 2`)
         if expr.may_have_paren_wo_args() {
-            let mut args = self.parse_operator_exprs()?;
+            let mut args = self.parse_operator_exprs(false)?;
             if !args.is_empty() {
                 self.skip_ws();
                 if let Some(lambda) = self.parse_opt_do_block()? {
@@ -128,20 +136,22 @@ impl<'a> Parser<'a> {
         }
 
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     // Returns `Some` if there is one of the following.
     // Otherwise, returns `None` and rewind the lexer position.
     // - `foo 1, 2, 3`
     // - `return 1`
+    // - `next 1`
+    // - `raise e`
     fn _try_parse_call_wo_paren(&mut self) -> Result<Option<AstExpression>, Error> {
         let token = self.current_token().clone();
         let cur = self.current_position();
         self.consume_token();
         self.set_lexer_state(LexerState::ExprArg);
         assert!(self.consume(Token::Space));
-        let mut args = self.parse_operator_exprs()?;
+        let mut args = self.parse_operator_exprs(false)?;
         self.debug_log(&format!("tried/args: {:?}", args));
         if !args.is_empty() {
             self.skip_ws();
@@ -158,6 +168,18 @@ impl<'a> Parser<'a> {
                     }
                     return Ok(Some(ast::return_expr(Some(args.pop().unwrap()))));
                 }
+                Token::KwNext => {
+                    if args.len() >= 2 {
+                        return Err(parse_error!(self, "`next' cannot take more than one args"));
+                    }
+                    return Ok(Some(ast::next_expr(Some(args.pop().unwrap()))));
+                }
+                Token::KwRaise => {
+                    if args.len() >= 2 {
+                        return Err(parse_error!(self, "`raise' cannot take more than one args"));
+                    }
+                    return Ok(Some(ast::raise_expr(args.pop().unwrap())));
+                }
                 _ => panic!("must not happen: {:?}", self.current_token())
             }
         }
@@ -167,15 +189,19 @@ impl<'a> Parser<'a> {
         Ok(None)
     }
 
-    /// Parse successive operator_exprs delimited by `,`
+    /// Parse successive method call args delimited by `,`
+    /// - `allow_splat` is true if the args are in parentheses (`foo *a` is
+    ///   parsed as `foo * a`). Hash literals are also allowed only in this
+    ///   case (`foo {` is a block)
     ///
     /// May return empty Vec if there are no values
-    fn parse_operator_exprs(&mut self) -> Result<Vec<AstExpression>, Error> {
+    fn parse_operator_exprs(&mut self, allow_splat: bool) -> Result<Vec<AstExpression>, Error> {
         self.lv += 1;
         self.debug_log("parse_operator_exprs");
         let mut v = vec![];
-        if self.next_nonspace_token().value_starts() {
-            v.push(self.parse_operator_expr()?);
+        let next = self.next_nonspace_token();
+        if next.value_starts() || (allow_splat && (next == Token::Mul || next == Token::LBrace)) {
+            v.push(self.parse_arg(allow_splat)?);
             loop {
                 self.skip_ws();
                 if !self.current_token_is(Token::Comma) {
@@ -183,25 +209,66 @@ impl<'a> Parser<'a> {
                 }
                 self.consume_token();
                 self.skip_wsn();
-                v.push(self.parse_operator_expr()?);
+                let arg = self.parse_arg(allow_splat)?;
+                let last = v.last().unwrap();
+                if last.is_splat_arg() {
+                    return Err(parse_error!(self, "`*' arg must be the last one"));
+                }
+                if last.is_keyword_arg() && !arg.is_keyword_arg() {
+                    return Err(parse_error!(
+                        self,
+                        "positional args cannot follow keyword args"
+                    ));
+                }
+                v.push(arg);
             }
         }
         self.lv -= 1;
         Ok(v)
     }
 
+    /// Parse a method call arg (eg. `1`, `x: 1` or `*a`)
+    fn parse_arg(&mut self, allow_splat: bool) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_arg");
+        if allow_splat && self.next_nonspace_token() == Token::Mul {
+            self.skip_ws();
+            let begin = self.current_position();
+            assert!(self.consume(Token::Mul));
+            let expr = self.parse_operator_expr()?;
+            self.lv -= 1;
+            return Ok(self.set_span(ast::splat_arg(expr), begin));
+        }
+        if let Token::LowerWord(name) = self.next_nonspace_token() {
+            self.skip_ws();
+            if self.peek_next_token() == Token::Colon {
+                let begin = self.current_position();
+                self.consume_token();
+                assert!(self.consume(Token::Colon));
+                self.skip_wsn();
+                let value = self.parse_operator_expr()?;
+                self.lv -= 1;
+                return Ok(self.set_span(ast::keyword_arg(name, value), begin));
+            }
+        }
+        let expr = self.parse_operator_expr()?;
+        self.lv -= 1;
+        Ok(expr)
+    }
+
     // operatorExpression:
     //   assignmentExpression |
     //   conditionalOperatorExpression
     fn parse_operator_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_operator_expr");
+        let begin = self.current_position();
         let mut expr = self.parse_conditional_expr()?;
         if expr.is_lhs() && self.next_nonspace_token().is_assignment_token() {
             expr = self.parse_assignment_expr(expr)?;
         }
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     // assignmentExpression:
@@ -238,10 +305,64 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// `a, b = pair`, `a, b = b, a`
+    /// - `first` is the first variable (`a`)
+    ///
+    /// Returns `None` and rewinds the lexer position if this is not a
+    /// multiple assignment (eg. `(a, b)`)
+    fn _try_parse_multiple_assignment(
+        &mut self,
+        first: &AstExpression,
+    ) -> Result<Option<AstExpression>, Error> {
+        let mut names = match &first.body {
+            AstExpressionBody::BareName(s) => vec![s.to_string()],
+            _ => return Ok(None),
+        };
+        let cur = self.current_position();
+        while self.next_nonspace_token() == Token::Comma {
+            self.skip_ws();
+            assert!(self.consume(Token::Comma));
+            self.skip_ws();
+            match self.current_token() {
+                Token::LowerWord(s) => {
+                    names.push(s.to_string());
+                    self.consume_token();
+                }
+                _ => break,
+            }
+        }
+        if self.next_nonspace_token() != Token::Equal {
+            // Failed. Rollback the lexer changes
+            self.rewind_to(cur);
+            self.set_lexer_state(LexerState::ExprEnd);
+            return Ok(None);
+        }
+        self.skip_ws();
+        assert!(self.consume(Token::Equal));
+        self.skip_wsn();
+        let mut rhs = vec![self.parse_operator_expr()?];
+        while self.next_nonspace_token() == Token::Comma {
+            self.skip_ws();
+            assert!(self.consume(Token::Comma));
+            self.skip_wsn();
+            rhs.push(self.parse_operator_expr()?);
+        }
+        if rhs.len() != 1 && rhs.len() != names.len() {
+            return Err(parse_error!(
+                self,
+                "tried to assign {} values to {} variables",
+                rhs.len(),
+                names.len()
+            ));
+        }
+        Ok(Some(ast::multiple_assignment(names, rhs)))
+    }
+
     /// `a ? b : c`
     fn parse_conditional_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_conditional_expr");
+        let begin = self.current_position();
         let expr = self.parse_range_expr()?;
         if self.next_nonspace_token() == Token::Question {
             self.skip_ws();
@@ -253,7 +374,8 @@ impl<'a> Parser<'a> {
             self.skip_wsn();
             let else_expr = self.parse_operator_expr()?;
             self.lv -= 1;
-            Ok(ast::if_expr(expr, vec![then_expr], Some(vec![else_expr])))
+            let if_expr = ast::if_expr(expr, vec![then_expr], Some(vec![else_expr]));
+            Ok(self.set_span(if_expr, begin))
         } else {
             self.lv -= 1;
             Ok(expr)
@@ -264,17 +386,20 @@ impl<'a> Parser<'a> {
     fn parse_range_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_range_expr");
-        let expr = self.parse_operator_or()?;
-        //        self.skip_ws();
-        //        match self.current_token() {
-        //            Token::symbol(s @ "..") | Token::symbol(s @ "...") => {
-        //                let inclusive = (s == "..");
-        //                self.skip_wsn();
-        //                let end_expr = self.parse_operator_or()?;
-        //                Ok(ast::range_expr(Some(expr), Some(end_expr), inclusive))
-        //            },
-        //            _ => Ok(expr)
-        //        }
+        let begin = self.current_position();
+        let mut expr = self.parse_operator_or()?;
+        let exclude_end = match self.next_nonspace_token() {
+            Token::DotDot => Some(false),
+            Token::DotDotDot => Some(true),
+            _ => None,
+        };
+        if let Some(exclude_end) = exclude_end {
+            self.skip_ws();
+            self.consume_token();
+            self.skip_wsn();
+            let last = self.parse_operator_or()?;
+            expr = self.set_span(ast::range_expr(expr, last, exclude_end), begin);
+        }
         self.lv -= 1;
         Ok(expr)
     }
@@ -283,6 +408,7 @@ impl<'a> Parser<'a> {
     fn parse_operator_or(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_operator_or");
+        let begin = self.current_position();
         let mut expr = self.parse_operator_and()?;
         let mut token = &self.next_nonspace_token();
         loop {
@@ -298,13 +424,14 @@ impl<'a> Parser<'a> {
             }
         }
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     /// `&&`
     fn parse_operator_and(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_operator_and");
+        let begin = self.current_position();
         let mut expr = self.parse_equality_expr()?;
         let mut token = &self.next_nonspace_token();
         loop {
@@ -320,13 +447,14 @@ impl<'a> Parser<'a> {
             }
         }
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     /// `==`, etc.
     fn parse_equality_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_equality_expr");
+        let begin = self.current_position();
         let left = self.parse_relational_expr()?;
         let op = match self.next_nonspace_token() {
             // TODO: <=> === =~ !~
@@ -349,13 +477,14 @@ impl<'a> Parser<'a> {
             call_eq
         };
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     /// <=, etc.
     fn parse_relational_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_relational_expr");
+        let begin = self.current_position();
         let mut expr = self.parse_bitwise_or()?; // additive (> >= < <=) additive
         let mut nesting = false;
         loop {
@@ -384,7 +513,7 @@ impl<'a> Parser<'a> {
             }
         }
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     fn parse_bitwise_or(&mut self) -> Result<AstExpression, Error> {
@@ -433,6 +562,7 @@ impl<'a> Parser<'a> {
     fn parse_unary_minus_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_unary_minus_expr");
+        let begin = self.current_position();
         //TODO:
         //  parse_unary_minus_expr
         //  parse_power_expr
@@ -444,13 +574,14 @@ impl<'a> Parser<'a> {
             self.parse_unary_expr()?
         };
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     // TODO: Parse ~, +
     fn parse_unary_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_unary_expr");
+        let begin = self.current_position();
         let expr = if self.consume(Token::Bang) {
             let target = self.parse_secondary_expr()?;
             ast::logical_not(target)
@@ -458,7 +589,7 @@ impl<'a> Parser<'a> {
             self.parse_secondary_expr()?
         };
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     /// Secondary expression
@@ -470,15 +601,22 @@ impl<'a> Parser<'a> {
     fn parse_secondary_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_secondary_expr");
+        let begin = self.current_position();
         let expr = match self.current_token() {
             Token::KwBreak => self.parse_break_expr(),
             Token::KwIf => self.parse_if_expr(),
             Token::KwUnless => self.parse_unless_expr(),
             Token::KwWhile => self.parse_while_expr(),
+            Token::KwUntil => self.parse_until_expr(),
+            Token::KwLoop => self.parse_loop_expr(),
+            Token::KwFor => self.parse_for_expr(),
+            Token::KwMatch => self.parse_match_expr(),
+            Token::KwCase => self.parse_case_expr(),
+            Token::KwBegin => self.parse_begin_expr(),
             _ => self.parse_primary_expr(),
         }?;
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     fn parse_break_expr(&mut self) -> Result<AstExpression, Error> {
@@ -588,11 +726,333 @@ impl<'a> Parser<'a> {
         Ok(ast::while_expr(cond_expr, body_exprs))
     }
 
+    fn parse_until_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_until_expr");
+        assert!(self.consume(Token::KwUntil));
+        self.skip_ws();
+        let cond_expr = self.parse_call_wo_paren()?;
+        self.skip_ws();
+        self.expect(Token::Separator)?;
+        let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+        self.skip_wsn();
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::while_expr(ast::logical_not(cond_expr), body_exprs))
+    }
+
+    /// `loop do ... end` (same as `while true ... end`)
+    fn parse_loop_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_loop_expr");
+        assert!(self.consume(Token::KwLoop));
+        self.skip_ws();
+        self.expect(Token::KwDo)?;
+        self.skip_wsn();
+        let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+        self.skip_wsn();
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::while_expr(ast::pseudo_variable(Token::KwTrue), body_exprs))
+    }
+
+    fn parse_for_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_for_expr");
+        assert!(self.consume(Token::KwFor));
+        self.skip_ws();
+        let var_name = match self.current_token() {
+            Token::LowerWord(s) => s.to_string(),
+            token => return Err(parse_error!(self, "invalid var name: {:?}", token)),
+        };
+        self.consume_token();
+        self.skip_ws();
+        self.expect(Token::KwIn)?;
+        self.skip_ws();
+        let iterable = self.parse_call_wo_paren()?;
+        self.skip_ws();
+        self.expect(Token::Separator)?;
+        let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+        self.skip_wsn();
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::for_expr(var_name, iterable, body_exprs))
+    }
+
+    fn parse_match_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_match_expr");
+        assert!(self.consume(Token::KwMatch));
+        self.skip_ws();
+        let cond_expr = self.parse_call_wo_paren()?;
+        self.skip_ws();
+        self.expect(Token::Separator)?;
+        self.skip_wsn();
+
+        let mut clauses = vec![];
+        while self.consume(Token::KwWhen) {
+            self.skip_ws();
+            clauses.push(self.parse_match_clause()?);
+            self.skip_wsn();
+        }
+        if clauses.is_empty() {
+            return Err(parse_error!(self, "match must have at least one `when' clause"));
+        }
+
+        let else_exprs = if self.consume(Token::KwElse) {
+            self.skip_wsn();
+            let exprs = self.parse_exprs(vec![Token::KwEnd])?;
+            self.skip_wsn();
+            Some(exprs)
+        } else {
+            None
+        };
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::match_expr(cond_expr, clauses, else_exprs))
+    }
+
+    fn parse_case_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_case_expr");
+        assert!(self.consume(Token::KwCase));
+        self.skip_ws();
+        let cond_expr = self.parse_call_wo_paren()?;
+        self.skip_ws();
+        self.expect(Token::Separator)?;
+        self.skip_wsn();
+
+        let mut clauses = vec![];
+        while self.consume(Token::KwWhen) {
+            self.skip_ws();
+            clauses.push(self.parse_case_clause()?);
+            self.skip_wsn();
+        }
+        if clauses.is_empty() {
+            return Err(parse_error!(self, "case must have at least one `when' clause"));
+        }
+
+        let else_exprs = if self.consume(Token::KwElse) {
+            self.skip_wsn();
+            let exprs = self.parse_exprs(vec![Token::KwEnd])?;
+            self.skip_wsn();
+            Some(exprs)
+        } else {
+            None
+        };
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(ast::case_expr(cond_expr, clauses, else_exprs))
+    }
+
+    /// Parse `1, 2 then ...` (the `when` should be consumed beforehand)
+    fn parse_case_clause(&mut self) -> Result<ast::AstCaseClause, Error> {
+        self.lv += 1;
+        self.debug_log("parse_case_clause");
+        let mut values = vec![self.parse_operator_expr()?];
+        loop {
+            self.skip_ws();
+            if !self.consume(Token::Comma) {
+                break;
+            }
+            self.skip_wsn();
+            values.push(self.parse_operator_expr()?);
+        }
+
+        // `then`
+        if self.consume(Token::KwThen) {
+            self.skip_wsn();
+        } else {
+            self.set_lexer_state(LexerState::ExprBegin); // +/- is always unary here
+            self.expect(Token::Separator)?;
+        }
+
+        let body_exprs = self.parse_exprs(vec![Token::KwWhen, Token::KwElse, Token::KwEnd])?;
+        self.lv -= 1;
+        Ok(ast::AstCaseClause { values, body_exprs })
+    }
+
+    /// Parse `Node(l, r) then ...` (the `when` should be consumed beforehand)
+    fn parse_match_clause(&mut self) -> Result<ast::AstMatchClause, Error> {
+        self.lv += 1;
+        self.debug_log("parse_match_clause");
+        // Class name
+        let mut names = vec![];
+        loop {
+            match self.current_token() {
+                Token::UpperWord(s) => {
+                    names.push(s.to_string());
+                    self.consume_token();
+                }
+                token => {
+                    return Err(parse_error!(
+                        self,
+                        "class name expected in `when' but got {:?}",
+                        token
+                    ))
+                }
+            }
+            if !self.consume(Token::ColonColon) {
+                break;
+            }
+        }
+
+        // Bindings (optional)
+        let mut bindings = vec![];
+        if self.consume(Token::LParen) {
+            self.skip_wsn();
+            loop {
+                match self.current_token() {
+                    Token::LowerWord(s) => {
+                        bindings.push(s.to_string());
+                        self.consume_token();
+                    }
+                    token => {
+                        return Err(parse_error!(
+                            self,
+                            "variable name expected in `when' but got {:?}",
+                            token
+                        ))
+                    }
+                }
+                self.skip_wsn();
+                if self.consume(Token::RParen) {
+                    break;
+                }
+                self.expect(Token::Comma)?;
+                self.skip_wsn();
+            }
+        }
+        self.skip_ws();
+
+        // `then`
+        if self.consume(Token::KwThen) {
+            self.skip_wsn();
+        } else {
+            self.set_lexer_state(LexerState::ExprBegin); // +/- is always unary here
+            self.expect(Token::Separator)?;
+        }
+
+        let body_exprs = self.parse_exprs(vec![Token::KwWhen, Token::KwElse, Token::KwEnd])?;
+        self.lv -= 1;
+        Ok(ast::AstMatchClause {
+            class_name: const_name(names),
+            bindings,
+            body_exprs,
+        })
+    }
+
+    fn parse_begin_expr(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_begin_expr");
+        assert!(self.consume(Token::KwBegin));
+        self.skip_ws();
+        self.set_lexer_state(LexerState::ExprBegin); // +/- is always unary here
+        self.expect(Token::Separator)?;
+        let body_exprs =
+            self.parse_exprs(vec![Token::KwRescue, Token::KwEnsure, Token::KwEnd])?;
+        let expr = self.parse_rescue_and_ensure(body_exprs)?;
+        self.expect(Token::KwEnd)?;
+        self.lv -= 1;
+        Ok(expr)
+    }
+
+    /// Parse the `rescue` and `ensure` clauses following `body_exprs`.
+    /// Stops before the `end`
+    pub(super) fn parse_rescue_and_ensure(
+        &mut self,
+        body_exprs: Vec<AstExpression>,
+    ) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_rescue_and_ensure");
+        let mut rescue_clauses = vec![];
+        while self.consume(Token::KwRescue) {
+            self.skip_ws();
+            rescue_clauses.push(self.parse_rescue_clause()?);
+            self.skip_wsn();
+        }
+        let ensure_exprs = if self.consume(Token::KwEnsure) {
+            self.skip_wsn();
+            let exprs = self.parse_exprs(vec![Token::KwEnd])?;
+            self.skip_wsn();
+            Some(exprs)
+        } else {
+            None
+        };
+        if !self.current_token_is(Token::KwEnd) {
+            return Err(parse_error!(
+                self,
+                "unexpected token in `begin': {:?}",
+                self.current_token()
+            ));
+        }
+        self.lv -= 1;
+        Ok(ast::begin_expr(body_exprs, rescue_clauses, ensure_exprs))
+    }
+
+    /// Parse `Foo, Bar => e then ...` (the `rescue` should be consumed beforehand)
+    fn parse_rescue_clause(&mut self) -> Result<ast::AstRescueClause, Error> {
+        self.lv += 1;
+        self.debug_log("parse_rescue_clause");
+        // Class names (optional)
+        let mut class_names = vec![];
+        while let Token::UpperWord(s) = self.current_token() {
+            let s = s.to_string();
+            self.consume_token();
+            class_names.push(self._parse_const_name(s)?);
+            self.skip_ws();
+            if !self.consume(Token::Comma) {
+                break;
+            }
+            self.skip_wsn();
+        }
+
+        // Variable (optional)
+        let var_name = if self.consume(Token::FatArrow) {
+            self.skip_ws();
+            match self.current_token() {
+                Token::LowerWord(s) => {
+                    let name = s.to_string();
+                    self.consume_token();
+                    self.skip_ws();
+                    Some(name)
+                }
+                token => {
+                    return Err(parse_error!(
+                        self,
+                        "variable name expected after `=>' but got {:?}",
+                        token
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+
+        // `then`
+        if self.consume(Token::KwThen) {
+            self.skip_wsn();
+        } else {
+            self.set_lexer_state(LexerState::ExprBegin); // +/- is always unary here
+            self.expect(Token::Separator)?;
+        }
+
+        let body_exprs =
+            self.parse_exprs(vec![Token::KwRescue, Token::KwEnsure, Token::KwEnd])?;
+        self.lv -= 1;
+        Ok(ast::AstRescueClause {
+            class_names,
+            var_name,
+            body_exprs,
+        })
+    }
+
     // prim . methodName argumentWithParentheses? block?
     // prim [ indexingArgumentList? ] not(EQUAL)
     fn parse_primary_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_primary_expr");
+        let begin = self.current_position();
         let mut expr = self.parse_atomic()?;
         loop {
             if self.consume(Token::LSqBracket) {
@@ -600,17 +1060,19 @@ impl<'a> Parser<'a> {
                 // TODO: parse multiple arguments
                 self.skip_wsn();
                 self.expect(Token::RSqBracket)?;
-                expr = ast::method_call(Some(expr), "[]", vec![arg], vec![], true, false);
+                let call = ast::method_call(Some(expr), "[]", vec![arg], vec![], true, false);
+                expr = self.set_span(call, begin.clone());
             } else if self.next_nonspace_token() == Token::Dot {
                 // TODO: Newline should also be allowed here (but Semicolon is not)
                 self.skip_ws();
-                expr = self.parse_method_chain(expr)?;
+                let call = self.parse_method_chain(expr)?;
+                expr = self.set_span(call, begin.clone());
             } else {
                 break;
             }
         }
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     /// Parse `.foo(args)` plus a block, if any
@@ -624,6 +1086,18 @@ impl<'a> Parser<'a> {
         // Method name
         let method_name = match self.current_token() {
             Token::LowerWord(s) => s.clone(),
+            Token::Number(s) => {
+                // Element of a tuple (`t.0`). Note that `t.0.1` is lexed
+                // as `t`, `.`, `0.1`
+                let indices = s.split('.').map(|i| i.to_string()).collect::<Vec<_>>();
+                self.consume_token();
+                let mut expr = expr;
+                for i in indices {
+                    expr = ast::method_call(Some(expr), &i, vec![], vec![], true, false);
+                }
+                self.lv -= 1;
+                return Ok(expr);
+            }
             token => return Err(parse_error!(self, "invalid method name: {:?}", token)),
         };
         self.consume_token();
@@ -711,7 +1185,7 @@ impl<'a> Parser<'a> {
         if self.consume(Token::RParen) {
             args = vec![]
         } else {
-            args = self.parse_operator_exprs()?;
+            args = self.parse_operator_exprs(true)?;
             self.skip_wsn();
             self.expect(Token::RParen)?;
         }
@@ -723,6 +1197,7 @@ impl<'a> Parser<'a> {
     fn parse_atomic(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_atomic");
+        let begin = self.current_position();
         let token = self.current_token();
         let expr = match token {
             Token::LowerWord(s) => {
@@ -734,6 +1209,21 @@ impl<'a> Parser<'a> {
                 self.consume_token();
                 Ok(ast::return_expr(None))
             }
+            Token::KwNext => {
+                self.consume_token();
+                Ok(ast::next_expr(None))
+            }
+            Token::KwRaise => {
+                self.consume_token();
+                if !self.current_token_is(Token::LParen) {
+                    return Err(parse_error!(self, "`raise' needs an exception"));
+                }
+                let mut args = self.parse_paren_and_args()?;
+                if args.len() != 1 {
+                    return Err(parse_error!(self, "`raise' takes exactly one arg"));
+                }
+                Ok(ast::raise_expr(args.pop().unwrap()))
+            }
             Token::UpperWord(s) => {
                 let name = s.to_string();
                 self.consume_token();
@@ -751,6 +1241,7 @@ impl<'a> Parser<'a> {
                 Ok(ast::ivar_ref(name))
             }
             Token::LSqBracket => self.parse_array_literal(),
+            Token::LBrace => self.parse_hash_literal(),
             Token::Number(_) => self.parse_decimal_literal(),
             Token::Str(_) => Ok(self.parse_string_literal()),
             Token::StrWithInterpolation { .. } => self.parse_string_with_interpolation(),
@@ -758,7 +1249,7 @@ impl<'a> Parser<'a> {
             token => Err(parse_error!(self, "unexpected token: {:?}", token)),
         }?;
         self.lv -= 1;
-        Ok(expr)
+        Ok(self.set_span(expr, begin))
     }
 
     // Method call with explicit parenthesis (eg. `foo(bar)`) optionally followed by a block
@@ -872,14 +1363,15 @@ impl<'a> Parser<'a> {
         assert!(self.consume(Token::KwFn));
         let params;
         if self.consume(Token::LParen) {
-            params = self.parse_params(false, vec![Token::RParen])?;
+            params = self.parse_params(false, true, vec![Token::RParen])?;
             self.skip_ws();
         } else {
             params = vec![];
         }
         self.skip_ws();
         self.expect(Token::LBrace)?;
-        let exprs = self.parse_exprs(vec![Token::RBrace])?;
+        let mut exprs = destructuring_exprs(&params);
+        exprs.append(&mut self.parse_exprs(vec![Token::RBrace])?);
         assert!(self.consume(Token::RBrace));
         self.lv -= 1;
         Ok(ast::lambda_expr(params, exprs, true))
@@ -892,11 +1384,76 @@ impl<'a> Parser<'a> {
         self.skip_wsn();
         let expr = self.parse_expr()?; // Should be parse_exprs() ?
         self.skip_wsn();
+        if self.current_token_is(Token::Comma) {
+            let tuple = self.parse_tuple_literal(expr)?;
+            self.lv -= 1;
+            return Ok(tuple);
+        }
         self.expect(Token::RParen)?;
         self.lv -= 1;
         Ok(expr)
     }
 
+    /// Parse the rest of a tuple literal (eg. `, 2)` of `(1, 2)`)
+    fn parse_tuple_literal(&mut self, first: AstExpression) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_tuple_literal");
+        let mut exprs = vec![first];
+        while self.consume(Token::Comma) {
+            self.skip_wsn();
+            exprs.push(self.parse_operator_expr()?);
+            self.skip_wsn();
+        }
+        self.expect(Token::RParen)?;
+        self.lv -= 1;
+        Ok(ast::tuple_literal(exprs))
+    }
+
+    /// `{k1 => v1, k2 => v2}`
+    fn parse_hash_literal(&mut self) -> Result<AstExpression, Error> {
+        self.lv += 1;
+        self.debug_log("parse_hash_literal");
+        assert!(self.consume(Token::LBrace));
+        let mut pairs = vec![];
+        self.skip_wsn();
+        loop {
+            match self.current_token() {
+                Token::RBrace => {
+                    self.consume_token();
+                    break;
+                }
+                Token::Comma => {
+                    return Err(parse_error!(self, "unexpected comma in a hash literal"))
+                }
+                _ => {
+                    let key = self.parse_operator_expr()?;
+                    self.skip_wsn();
+                    self.expect(Token::FatArrow)?;
+                    self.skip_wsn();
+                    let value = self.parse_operator_expr()?;
+                    pairs.push((key, value));
+                    self.skip_wsn();
+                    match self.current_token() {
+                        Token::Comma => {
+                            self.consume_token();
+                            self.skip_wsn();
+                        }
+                        Token::RBrace => (),
+                        token => {
+                            return Err(parse_error!(
+                                self,
+                                "unexpected token `{:?}' in a hash literal",
+                                token
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        self.lv -= 1;
+        Ok(ast::hash_literal(pairs))
+    }
+
     fn parse_array_literal(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_array_literal");
@@ -1038,6 +1595,7 @@ impl<'a> Parser<'a> {
     ) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log(name);
+        let begin = self.current_position();
         let mut left = func(self)?;
         loop {
             let t = self.next_nonspace_token();
@@ -1052,7 +1610,7 @@ impl<'a> Parser<'a> {
             self.consume_token(); // Consume t
             self.skip_wsn(); // TODO: should ban ';' here
             let right = func(self)?;
-            left = ast::bin_op_expr(left, op, right)
+            left = self.set_span(ast::bin_op_expr(left, op, right), begin.clone());
         }
     }
 
@@ -1091,7 +1649,8 @@ impl<'a> Parser<'a> {
             vec![]
         };
         self.skip_wsn();
-        let body_exprs = self.parse_exprs(vec![Token::KwEnd])?;
+        let mut body_exprs = destructuring_exprs(&block_params);
+        body_exprs.append(&mut self.parse_exprs(vec![Token::KwEnd])?);
         self.expect(Token::KwEnd)?;
         self.lv -= 1;
         Ok(ast::lambda_expr(block_params, body_exprs, false))
@@ -1109,7 +1668,8 @@ impl<'a> Parser<'a> {
             vec![]
         };
         self.skip_wsn();
-        let body_exprs = self.parse_exprs(vec![Token::RBrace])?;
+        let mut body_exprs = destructuring_exprs(&block_params);
+        body_exprs.append(&mut self.parse_exprs(vec![Token::RBrace])?);
         self.expect(Token::RBrace)?;
         self.lv -= 1;
         Ok(ast::lambda_expr(block_params, body_exprs, false))
@@ -1121,8 +1681,22 @@ impl<'a> Parser<'a> {
         self.debug_log("parse_block_params");
         self.expect(Token::Or)?;
         self.skip_wsn();
-        let params = self.parse_params(false, vec![Token::Or])?;
+        let params = self.parse_params(false, true, vec![Token::Or])?;
         self.lv -= 1;
         Ok(params)
     }
 }
+
+/// `|(k, v): (K, V)|` is equivalent to `|x: (K, V)|` followed by
+/// `k, v = x` (where `x` is the hidden name of the param).
+/// Returns expressions like `k, v = x`
+fn destructuring_exprs(params: &[Param]) -> Vec<AstExpression> {
+    params
+        .iter()
+        .filter_map(|param| {
+            param.destructure.as_ref().map(|names| {
+                ast::multiple_assignment(names.clone(), vec![ast::bare_name(&param.name)])
+            })
+        })
+        .collect()
+}
@@ -46,9 +46,9 @@ pub enum LexerState {
 
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct Cursor {
-    line: usize,
-    col: usize,
-    pos: usize, // Number of bytes from the begginning of the file
+    pub line: usize,
+    pub col: usize,
+    pub pos: usize, // Number of bytes from the begginning of the file
 }
 
 impl Cursor {
@@ -339,8 +339,20 @@ impl<'a> Lexer<'a> {
             None => self.cur.pos,
         };
         let s = &self.src[begin..next_cur.pos];
+        // Keywords can be used as method names (eg. `obj.class`)
+        let is_method_name = self.current_token == Token::Dot
+            || (self.state == LexerState::MethodName && s != "self");
+        if is_method_name {
+            return (Token::LowerWord(s.to_string()), Some(LexerState::ExprEnd));
+        }
         let (token, state) = match s {
             "class" => (Token::KwClass, LexerState::ExprBegin),
+            "enum" => (Token::KwEnum, LexerState::ExprBegin),
+            "case" => (Token::KwCase, LexerState::ExprBegin),
+            "module" => (Token::KwModule, LexerState::ExprBegin),
+            "interface" => (Token::KwInterface, LexerState::ExprBegin),
+            "include" => (Token::KwInclude, LexerState::ExprBegin),
+            "require" => (Token::KwRequire, LexerState::ExprBegin),
             "end" => (Token::KwEnd, LexerState::ExprEnd),
             "def" => (Token::KwDef, LexerState::ExprBegin),
             "var" => (Token::KwVar, LexerState::ExprBegin),
@@ -359,8 +371,19 @@ impl<'a> Lexer<'a> {
                 }
             }
             "while" => (Token::KwWhile, LexerState::ExprBegin),
+            "until" => (Token::KwUntil, LexerState::ExprBegin),
+            "loop" => (Token::KwLoop, LexerState::ExprBegin),
+            "for" => (Token::KwFor, LexerState::ExprBegin),
+            "in" => (Token::KwIn, LexerState::ExprBegin),
+            "match" => (Token::KwMatch, LexerState::ExprBegin),
+            "when" => (Token::KwWhen, LexerState::ExprBegin),
             "break" => (Token::KwBreak, LexerState::ExprEnd),
+            "next" => (Token::KwNext, LexerState::ExprBegin),
             "return" => (Token::KwReturn, LexerState::ExprBegin),
+            "begin" => (Token::KwBegin, LexerState::ExprBegin),
+            "rescue" => (Token::KwRescue, LexerState::ExprBegin),
+            "ensure" => (Token::KwEnsure, LexerState::ExprBegin),
+            "raise" => (Token::KwRaise, LexerState::ExprBegin),
             "then" => (Token::KwThen, LexerState::ExprBegin),
             "else" => (Token::KwElse, LexerState::ExprBegin),
             "elsif" => (Token::KwElsif, LexerState::ExprBegin),
@@ -472,6 +495,9 @@ impl<'a> Lexer<'a> {
                 if c2 == Some('=') {
                     next_cur.proceed(self.src);
                     (Token::EqEq, LexerState::ExprBegin)
+                } else if c2 == Some('>') {
+                    next_cur.proceed(self.src);
+                    (Token::FatArrow, LexerState::ExprBegin)
                 } else {
                     (Token::Equal, LexerState::ExprBegin)
                 }
@@ -523,7 +549,19 @@ impl<'a> Lexer<'a> {
                     (Token::GreaterThan, LexerState::ExprBegin)
                 }
             }
-            '.' => (Token::Dot, LexerState::ExprBegin),
+            '.' => {
+                if c2 == Some('.') {
+                    next_cur.proceed(self.src);
+                    if next_cur.peek(self.src) == Some('.') {
+                        next_cur.proceed(self.src);
+                        (Token::DotDotDot, LexerState::ExprBegin)
+                    } else {
+                        (Token::DotDot, LexerState::ExprBegin)
+                    }
+                } else {
+                    (Token::Dot, LexerState::ExprBegin)
+                }
+            }
             '@' => (Token::At, LexerState::ExprBegin),
             '~' => (Token::Tilde, LexerState::ExprBegin),
             '?' => (Token::Question, LexerState::ExprBegin),
@@ -17,29 +17,39 @@ mod expression_parser;
 pub mod lexer;
 pub mod token;
 use crate::ast;
+use crate::error;
 use crate::error::Error;
 use crate::parser::lexer::Lexer;
 use crate::parser::lexer::LexerState;
 pub use crate::parser::token::Token;
+use std::rc::Rc;
 
 pub struct Parser<'a> {
     pub lexer: Lexer<'a>,
+    /// Path of the file being parsed (empty if not from a file)
+    pub filepath: Rc<String>,
     /// For debug print
     pub lv: usize,
+    /// Errors recovered from so far (reported after parsing)
+    pub errors: Vec<Error>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(src: &str) -> Parser {
         Parser {
             lexer: Lexer::new(src),
+            filepath: Rc::new("".to_string()),
             lv: 0,
+            errors: vec![],
         }
     }
 
     pub fn new_with_state(src: &str, state: LexerState) -> Parser {
         Parser {
             lexer: Lexer::new_with_state(src, state),
+            filepath: Rc::new("".to_string()),
             lv: 0,
+            errors: vec![],
         }
     }
 
@@ -48,10 +58,23 @@ impl<'a> Parser<'a> {
         parser.parse_program()
     }
 
+    /// Parse the contents of a file. `filepath` is used for error messages
+    pub fn parse_file(src: &str, filepath: &str) -> Result<ast::Program, Error> {
+        let mut parser = Parser::new(src);
+        parser.filepath = Rc::new(filepath.to_string());
+        parser.parse_program()
+    }
+
     fn parse_program(&mut self) -> Result<ast::Program, Error> {
         self.skip_wsn();
         let toplevel_items = self.parse_toplevel_items()?;
-        self.expect_eof()?;
+        if let Err(e) = self.expect_eof() {
+            self.errors.push(e);
+        }
+        if !self.errors.is_empty() {
+            let errs = std::mem::take(&mut self.errors);
+            return Err(error::errors(errs));
+        }
         Ok(ast::Program { toplevel_items })
     }
 
@@ -69,20 +92,44 @@ impl<'a> Parser<'a> {
     fn parse_toplevel_items(&mut self) -> Result<Vec<ast::TopLevelItem>, Error> {
         let mut items = vec![];
         loop {
-            match self.current_token() {
-                Token::KwClass => {
-                    items.push(ast::TopLevelItem::Def(self.parse_class_definition()?));
-                }
-                Token::KwDef => {
-                    items.push(ast::TopLevelItem::Def(self.parse_method_definition()?));
-                }
+            let begin = self.current_position();
+            let result = match self.current_token() {
+                Token::KwClass => self.parse_class_definition().map(ast::TopLevelItem::Def),
+                Token::KwEnum => self.parse_enum_definition().map(ast::TopLevelItem::Def),
+                Token::KwModule => self.parse_module_definition().map(ast::TopLevelItem::Def),
+                Token::KwInterface => self
+                    .parse_interface_definition()
+                    .map(ast::TopLevelItem::Def),
+                Token::KwDef => self.parse_method_definition().map(ast::TopLevelItem::Def),
+                Token::KwRequire => self.parse_require(),
                 Token::Eof | Token::KwEnd => break,
-                _ => {
-                    items.push(ast::TopLevelItem::Expr(self.parse_expr()?));
-                }
+                _ => self.parse_expr().map(ast::TopLevelItem::Expr),
+            };
+            match result {
+                Ok(item) => items.push(item),
+                Err(e) => self.recover(e, &begin),
             }
             self.skip_wsn();
         }
         Ok(items)
     }
+
+    // Parse `require "foo/bar"`
+    fn parse_require(&mut self) -> Result<ast::TopLevelItem, Error> {
+        self.debug_log("parse_require");
+        assert!(self.consume(Token::KwRequire));
+        self.skip_ws();
+        match self.current_token() {
+            Token::Str(s) => {
+                let path = s.to_string();
+                self.consume_token();
+                Ok(ast::TopLevelItem::Require(path))
+            }
+            token => Err(parse_error!(
+                self,
+                "require needs a string literal but got {:?}",
+                token
+            )),
+        }
+    }
 }
@@ -23,6 +23,7 @@ pub enum Token {
     UnaryPlus,   //  +a
     BinaryPlus,  //  a + b
     RightArrow,  //  ->
+    FatArrow,    //  =>
     UnaryMinus,  //  -a
     BinaryMinus, //  a - b
     Mul,         //  *
@@ -37,6 +38,8 @@ pub enum Token {
     Equal,       //  =
     Bang,        //  !
     Dot,         //  .
+    DotDot,      //  ..
+    DotDotDot,   //  ...
     At,          //  @
     Tilde,       //  ~
     Question,    //  ?
@@ -69,14 +72,31 @@ pub enum Token {
     SetMethod,    //  []=
     // Keywords
     KwClass,
+    KwEnum,
+    KwCase,
+    KwModule,
+    KwInterface,
+    KwInclude,
+    KwRequire,
     KwEnd,
     KwDef,
     KwVar,
     KwIf,
     KwUnless,
     KwWhile,
+    KwUntil,
+    KwLoop,
+    KwFor,
+    KwIn,
+    KwMatch,
+    KwWhen,
     KwBreak,
+    KwNext,
     KwReturn,
+    KwBegin,
+    KwRescue,
+    KwEnsure,
+    KwRaise,
     KwThen,
     KwElse,
     KwElsif,
@@ -145,6 +165,7 @@ impl Token {
             Token::UnaryPlus => true,    //  +
             Token::BinaryPlus => false,  //  +
             Token::RightArrow => false,  //  ->
+            Token::FatArrow => false,    //  =>
             Token::UnaryMinus => true,   //  -
             Token::BinaryMinus => false, //  -
             Token::Mul => false,         //  *
@@ -159,6 +180,8 @@ impl Token {
             Token::Equal => false,       //  =
             Token::Bang => true,         //  !
             Token::Dot => false,         //  .
+            Token::DotDot => false,      //  ..
+            Token::DotDotDot => false,   //  ...
             Token::At => true,           //  @
             Token::Tilde => true,        //  ~
             Token::Question => false,    //  ?
@@ -191,14 +214,31 @@ impl Token {
             Token::SetMethod => false,    //  []=
             // Keywords
             Token::KwClass => false,
+            Token::KwEnum => false,
+            Token::KwCase => true,
+            Token::KwModule => false,
+            Token::KwInterface => false,
+            Token::KwInclude => false,
+            Token::KwRequire => false,
             Token::KwEnd => false,
             Token::KwDef => false,
             Token::KwVar => false,
             Token::KwIf => true,
             Token::KwUnless => true,
             Token::KwWhile => true,
+            Token::KwUntil => true,
+            Token::KwLoop => true,
+            Token::KwFor => true,
+            Token::KwIn => false,
+            Token::KwMatch => true,
+            Token::KwWhen => false,
             Token::KwBreak => false,
+            Token::KwNext => false,
             Token::KwReturn => false,
+            Token::KwBegin => true,
+            Token::KwRescue => false,
+            Token::KwEnsure => false,
+            Token::KwRaise => false,
             Token::KwThen => false,
             Token::KwElse => false,
             Token::KwElsif => false,
@@ -1,8 +1,11 @@
+use crate::ast;
 use crate::error::*;
+use crate::parser::Parser;
 use log;
+use std::collections::HashSet;
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Generate .ll from .sk
@@ -12,11 +15,12 @@ pub fn compile<P: AsRef<Path>>(filepath: P) -> Result<(), Error> {
         .to_str()
         .expect("failed to unwrap filepath")
         .to_string();
-    let builtin = wrap_error(load_builtin())?;
-    let str = builtin
-        + &fs::read_to_string(filepath)
-            .map_err(|e| runner_error(format!("{} is not utf8", path), Box::new(e)))?;
-    let ast = crate::parser::Parser::parse(&str)?;
+    let mut loader = Loader::default();
+    loader.load_builtins()?;
+    loader.load(filepath.as_ref())?;
+    let ast = ast::Program {
+        toplevel_items: loader.toplevel_items,
+    };
     log::debug!("created ast");
     let corelib = crate::corelib::Corelib::create();
     log::debug!("loaded corelib");
@@ -29,21 +33,102 @@ pub fn compile<P: AsRef<Path>>(filepath: P) -> Result<(), Error> {
     Ok(())
 }
 
-fn load_builtin() -> Result<String, Box<dyn std::error::Error>> {
-    let mut s = String::new();
+/// Type-check a program given as a string (with the builtin classes)
+/// without generating code. Used for testing compile errors
+pub fn check_src(src: &str) -> Result<(), Error> {
+    let mut loader = Loader::default();
+    loader.load_builtins()?;
+    loader
+        .toplevel_items
+        .extend(Parser::parse(src)?.toplevel_items);
+    let ast = ast::Program {
+        toplevel_items: loader.toplevel_items,
+    };
+    let corelib = crate::corelib::Corelib::create();
+    crate::hir::build(ast, corelib)?;
+    Ok(())
+}
+
+/// Returns the paths of builtin/*.sk
+fn builtin_paths() -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut paths = vec![];
     let dir =
         fs::read_dir("builtin").map_err(|e| runner_error("./builtin not found", Box::new(e)))?;
     for item in dir {
         let pathbuf = item?.path();
-        let path = pathbuf
-            .to_str()
-            .ok_or_else(|| plain_runner_error("Filename not utf8"))?;
-        if path.ends_with(".sk") {
-            s += &fs::read_to_string(path)
-                .map_err(|e| runner_error(format!("failed to load {}", path), Box::new(e)))?;
+        if pathbuf.extension().map_or(false, |ext| ext == "sk") {
+            paths.push(pathbuf);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Parses .sk files and the files `require`d from them
+#[derive(Default)]
+struct Loader {
+    /// Toplevel items of the loaded files (without `require`)
+    toplevel_items: Vec<ast::TopLevelItem>,
+    /// Files already loaded (each file is loaded only once)
+    loaded: HashSet<PathBuf>,
+    /// Files being loaded (to detect circular `require`)
+    loading: Vec<PathBuf>,
+}
+
+impl Loader {
+    /// Load the builtin classes (the generated tuple classes and builtin/*.sk)
+    fn load_builtins(&mut self) -> Result<(), Error> {
+        let tuples = Parser::parse_file(&crate::corelib::tuple::tuples_src(), "<tuples>")?;
+        self.toplevel_items.extend(tuples.toplevel_items);
+        for builtin_path in wrap_error(builtin_paths())? {
+            self.load(&builtin_path)?;
+        }
+        Ok(())
+    }
+
+    /// Parse the file and add its items to `self.toplevel_items`.
+    /// Items of a required file are placed at the position of the `require`
+    fn load(&mut self, filepath: &Path) -> Result<(), Error> {
+        let path = fs::canonicalize(filepath).map_err(|e| {
+            runner_error(format!("{} not found", filepath.display()), Box::new(e))
+        })?;
+        if self.loading.contains(&path) {
+            let mut chain = self
+                .loading
+                .iter()
+                .skip_while(|p| **p != path)
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>();
+            chain.push(path.display().to_string());
+            return Err(plain_runner_error(format!(
+                "circular require: {}",
+                chain.join(" -> ")
+            )));
+        }
+        if !self.loaded.insert(path.clone()) {
+            return Ok(());
+        }
+
+        let src = fs::read_to_string(&path).map_err(|e| {
+            runner_error(format!("failed to load {}", filepath.display()), Box::new(e))
+        })?;
+        let prog = Parser::parse_file(&src, &filepath.display().to_string())?;
+        self.loading.push(path.clone());
+        for item in prog.toplevel_items {
+            match item {
+                ast::TopLevelItem::Require(name) => {
+                    let mut required = path.parent().unwrap().join(name);
+                    if required.extension().is_none() {
+                        required.set_extension("sk");
+                    }
+                    self.load(&required)?;
+                }
+                _ => self.toplevel_items.push(item),
+            }
         }
+        self.loading.pop();
+        Ok(())
     }
-    Ok(s)
 }
 
 /// Execute compiled .ll
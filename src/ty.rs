@@ -52,7 +52,9 @@ impl TermTy {
                 base_name,
                 type_args,
             } => format!("Meta:{}<{}>", base_name, _dbg_type_args(type_args)),
-            TyParamRef { kind, name, idx } => {
+            TyParamRef {
+                kind, name, idx, ..
+            } => {
                 let k = match kind {
                     TyParamKind::Class => "C",
                     TyParamKind::Method => "M",
@@ -105,7 +107,16 @@ pub enum TyBody {
         kind: TyParamKind,
         name: String,
         idx: usize,
+        // eg. `Comparable<T>` for `T : Comparable<T>` (`Object` if not specified)
+        upper_bound: Box<TermTy>,
     },
+    // Type of an expression which has a compile error.
+    // Conforms to any type (and vice versa) so that one error does not
+    // cause other errors.
+    // The error is already reported when an expression of this type is
+    // made, so the code which finds one only needs to give up quietly
+    // (eg. return `Hir::error_expression()`)
+    TyError,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -128,13 +139,34 @@ impl TermTy {
     }
 
     // Returns true when this is the Never type
+    // (the error type is regarded as Never too)
     pub fn is_never_type(&self) -> bool {
         match self.body {
             TyRaw => (self.fullname.0 == "Never"),
+            TyError => true,
             _ => false,
         }
     }
 
+    // Returns true when this is the type of an erroneous expression
+    pub fn is_error_type(&self) -> bool {
+        match self.body {
+            TyError => true,
+            _ => false,
+        }
+    }
+
+    // Returns the number of elements if this is a tuple type
+    pub fn tuple_size(&self) -> Option<usize> {
+        match &self.body {
+            TySpe {
+                base_name,
+                type_args,
+            } if *base_name == tuple_class_name(type_args.len()) => Some(type_args.len()),
+            _ => None,
+        }
+    }
+
     // Returns ret_ty if this is any of Fn0, .., Fn9
     pub fn fn_x_info(&self) -> Option<TermTy> {
         match &self.body {
@@ -179,14 +211,17 @@ impl TermTy {
     /// an object of the type `self` is included in the set of objects represented by the type `other`
     pub fn conforms_to(&self, other: &TermTy, class_dict: &ClassDict) -> bool {
         // `Never` is bottom type (i.e. subclass of any class)
-        if self.is_never_type() {
+        if self.is_never_type() || other.is_error_type() {
             return true
         }
-        if let TyParamRef { name, .. } = &self.body {
+        if let TyParamRef {
+            name, upper_bound, ..
+        } = &self.body
+        {
             if let TyParamRef { name: name2, .. } = &other.body {
                 name == name2
             } else {
-                other == &ty::raw("Object") // The upper bound
+                upper_bound.conforms_to(other, class_dict)
             }
         } else if let TyParamRef { name, .. } = &other.body {
             if let TyParamRef { name: name2, .. } = &self.body {
@@ -204,12 +239,19 @@ impl TermTy {
                 type_args: a2,
             } = &other.body
             {
-                if base_name != b2 {
-                    return false;
-                } // TODO: Relax this condition
+                // eg. Passing a `Expr::Value<Int>` for `Expr<Int>`
+                let is_enum_case =
+                    class_dict.is_enum_case_of(&class_fullname(base_name), &class_fullname(b2));
+                if base_name != b2 && !is_enum_case {
+                    // eg. Passing a `Pair<Int>` for `Comparable<Pair<Int>>`
+                    return class_dict.implements(self, other);
+                }
                 for (i, a) in type_args.iter().enumerate() {
-                    // Invariant
-                    if a.equals_to(&a2[i]) || a2[i].is_void_type() {
+                    // Invariant (`Never` is allowed for enum cases without fields)
+                    if a.equals_to(&a2[i])
+                        || a2[i].is_void_type()
+                        || (is_enum_case && a.is_never_type())
+                    {
                         // ok
                     } else {
                         return false;
@@ -219,16 +261,47 @@ impl TermTy {
             } else {
                 // eg. Passing a `Array<String>` for `Object`
                 let base = ty::raw(base_name);
-                class_dict.is_descendant(&base, other)
+                class_dict.is_descendant(&base, other) || class_dict.implements(self, other)
             }
         } else {
-            self.equals_to(other) || class_dict.is_descendant(self, other)
+            self.equals_to(other)
+                || class_dict.is_descendant(self, other)
+                || class_dict.implements(self, other)
         }
     }
 
     /// Return true if two types are identical
+    /// (upper bounds of type parameters are not compared because a bound
+    /// may refer to the type parameter itself eg. `T : Comparable<T>`)
     pub fn equals_to(&self, other: &TermTy) -> bool {
-        self == other
+        match (&self.body, &other.body) {
+            (
+                TyParamRef {
+                    kind, name, idx, ..
+                },
+                TyParamRef {
+                    kind: kind2,
+                    name: name2,
+                    idx: idx2,
+                    ..
+                },
+            ) => kind == kind2 && name == name2 && idx == idx2,
+            (
+                TySpe {
+                    base_name,
+                    type_args,
+                },
+                TySpe {
+                    base_name: b2,
+                    type_args: a2,
+                },
+            ) => {
+                base_name == b2
+                    && type_args.len() == a2.len()
+                    && type_args.iter().zip(a2).all(|(a, b)| a.equals_to(b))
+            }
+            _ => self == other,
+        }
     }
 
     /// Return the supertype of self
@@ -244,8 +317,15 @@ impl TermTy {
                 }
             }
             TyClass => Some(ty::raw("Object")),
-            TySpe { base_name, .. } => {
+            TySpe {
+                base_name,
+                type_args,
+            } => {
                 match class_dict.get_superclass(&class_fullname(base_name)) {
+                    // eg. `Expr<Int>` for `Expr::Value<Int>`
+                    Some(scls) if scls.is_enum => {
+                        Some(ty::spe(&scls.fullname.0, type_args.clone()))
+                    }
                     Some(scls) => Some(ty::raw(&scls.fullname.0)),
                     None => panic!("unexpected"),
                 }
@@ -313,7 +393,7 @@ impl TermTy {
 
     pub fn upper_bound(&self) -> TermTy {
         match &self.body {
-            TyParamRef { .. } => ty::raw("Object"),
+            TyParamRef { upper_bound, .. } => (**upper_bound).clone(),
             TySpe {
                 base_name,
                 type_args,
@@ -335,6 +415,9 @@ impl TermTy {
 
 pub fn raw(fullname: &str) -> TermTy {
     debug_assert!(!fullname.contains('<'), fullname.to_string());
+    if fullname == "Class" {
+        return ty::class();
+    }
     TermTy {
         fullname: class_fullname(fullname),
         body: TyRaw,
@@ -358,6 +441,14 @@ pub fn class() -> TermTy {
     }
 }
 
+/// The type of an expression which has a compile error
+pub fn error() -> TermTy {
+    TermTy {
+        fullname: class_fullname("Never"),
+        body: TyError,
+    }
+}
+
 pub fn spe(base_name: &str, type_args: Vec<TermTy>) -> TermTy {
     let tyarg_names = type_args
         .iter()
@@ -387,21 +478,14 @@ pub fn spe_meta(base_name: &str, type_args: Vec<TermTy>) -> TermTy {
 }
 
 /// Create the type of return value of `.new` method of the class
-pub fn return_type_of_new(classname: &ClassFullname, typarams: &[String]) -> TermTy {
+pub fn return_type_of_new(classname: &ClassFullname, typarams: &[TyParam]) -> TermTy {
     if typarams.is_empty() {
         ty::raw(&classname.0)
     } else {
         let args = typarams
             .iter()
             .enumerate()
-            .map(|(i, s)| TermTy {
-                fullname: class_fullname(s),
-                body: TyParamRef {
-                    kind: TyParamKind::Class,
-                    name: s.to_string(),
-                    idx: i,
-                },
-            })
+            .map(|(i, param)| typaram(param, TyParamKind::Class, i))
             .collect::<Vec<_>>();
         ty::spe(&classname.0, args)
     }
@@ -412,12 +496,24 @@ pub fn ary(type_arg: TermTy) -> TermTy {
     spe("Array", vec![type_arg])
 }
 
-pub fn typaram(name: impl Into<String>, kind: TyParamKind, idx: usize) -> TermTy {
-    let s = name.into();
+/// Max number of elements of a tuple (`Tuple2`, ..., `Tuple9`)
+pub const MAX_TUPLE_SIZE: usize = 9;
+
+/// Name of the class of the tuples with `n` elements (eg. `Tuple2`)
+pub fn tuple_class_name(n: usize) -> String {
+    format!("Tuple{}", n)
+}
+
+pub fn typaram(param: &TyParam, kind: TyParamKind, idx: usize) -> TermTy {
     TermTy {
-        // TODO: s is not a class name. `fullname` should be just a String
-        fullname: class_fullname(s.clone()),
-        body: TyParamRef { kind, name: s, idx },
+        // TODO: name is not a class name. `fullname` should be just a String
+        fullname: class_fullname(&param.name),
+        body: TyParamRef {
+            kind,
+            name: param.name.clone(),
+            idx,
+            upper_bound: Box::new(param.upper_bound.clone()),
+        },
     }
 }
 
@@ -426,4 +522,16 @@ pub fn typaram(name: impl Into<String>, kind: TyParamKind, idx: usize) -> TermTy
 #[derive(Debug, PartialEq, Clone)]
 pub struct TyParam {
     pub name: String,
+    /// eg. `Comparable<T>` for `T : Comparable<T>` (`Object` if not specified)
+    pub upper_bound: TermTy,
+}
+
+impl TyParam {
+    /// Create a type parameter without an explicit upper bound
+    pub fn new(name: impl Into<String>) -> TyParam {
+        TyParam {
+            name: name.into(),
+            upper_bound: ty::raw("Object"),
+        }
+    }
 }
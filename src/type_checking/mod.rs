@@ -1,6 +1,7 @@
 use crate::error::Error;
 use crate::hir;
 use crate::hir::*;
+use crate::names::*;
 use crate::ty;
 use crate::ty::*;
 
@@ -28,7 +29,7 @@ pub fn check_return_value(
 }
 
 pub fn check_logical_operator_ty(ty: &TermTy, on: &str) -> Result<(), Error> {
-    if *ty == ty::raw("Bool") {
+    if *ty == ty::raw("Bool") || ty.is_error_type() {
         Ok(())
     } else {
         Err(type_error!("{} must be bool but got {:?}", on, ty.fullname))
@@ -36,7 +37,7 @@ pub fn check_logical_operator_ty(ty: &TermTy, on: &str) -> Result<(), Error> {
 }
 
 pub fn check_condition_ty(ty: &TermTy, on: &str) -> Result<(), Error> {
-    if *ty == ty::raw("Bool") {
+    if *ty == ty::raw("Bool") || ty.is_error_type() {
         Ok(())
     } else {
         Err(type_error!(
@@ -62,6 +63,135 @@ pub fn check_if_clauses_ty(then_ty: &TermTy, else_ty: &TermTy) -> Result<(), Err
     }
 }
 
+/// Check if the clauses of a `match` covers all the subclasses of `class`
+pub fn check_match_exhaustiveness(
+    class_dict: &ClassDict,
+    class: &ClassFullname,
+    covered: &[ClassFullname],
+) -> Result<(), Error> {
+    match find_uncovered_class(class_dict, class, covered) {
+        Some(name) => Err(type_error!(
+            "match on {} is not exhaustive ({} is not covered)",
+            class,
+            name
+        )
+        .with_help(format!("add `when {}` or `else`", name.0))),
+        None => Ok(()),
+    }
+}
+
+/// Return a class which is not covered by `covered`, if any.
+/// An enum is regarded as covered when all of its cases are covered.
+/// Other classes may have instances of their own and therefore must be
+/// covered by themselves
+fn find_uncovered_class(
+    class_dict: &ClassDict,
+    class: &ClassFullname,
+    covered: &[ClassFullname],
+) -> Option<ClassFullname> {
+    if covered.contains(class) {
+        return None;
+    }
+    let subclasses = class_dict.subclasses_of(class);
+    if subclasses.is_empty() || !class_dict.is_enum(class) {
+        return Some(class.clone());
+    }
+    subclasses
+        .iter()
+        .find_map(|sub| find_uncovered_class(class_dict, sub, covered))
+}
+
+/// Check the type of the default value of a param
+pub fn check_default_value(
+    class_dict: &ClassDict,
+    param: &MethodParam,
+    ty: &TermTy,
+) -> Result<(), Error> {
+    if ty.is_error_type() || ty.conforms_to(&param.ty, class_dict) {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "the default value of `{}' should be {} but got {}",
+            param.name,
+            param.ty,
+            ty
+        ))
+    }
+}
+
+/// Check that `sig` has default values for the params which have one in
+/// `super_sig` (the method overridden by `sig`)
+pub fn check_override_defaults(
+    sig: &MethodSignature,
+    super_sig: &MethodSignature,
+) -> Result<(), Error> {
+    for (param, super_param) in sig.params.iter().zip(super_sig.params.iter()) {
+        if super_param.default_expr.is_some() && param.default_expr.is_none() {
+            return Err(type_error!(
+                "param `{}' of {} needs a default value because {} has one",
+                param.name,
+                sig.fullname,
+                super_sig.fullname
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check the argument of `raise` is an exception
+pub fn check_raise_arg_ty(class_dict: &ClassDict, ty: &TermTy) -> Result<(), Error> {
+    if ty.is_error_type() || ty.conforms_to(&ty::raw("Exception"), class_dict) {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "`raise' needs an Exception or a String but got {}",
+            ty
+        ))
+    }
+}
+
+/// Check the class in a `rescue` clause is a subclass of `Exception`
+pub fn check_rescue_class(class_dict: &ClassDict, class: &ClassFullname) -> Result<(), Error> {
+    let exception = class_fullname("Exception");
+    if class_dict.descendant_classes(&exception).contains(class) {
+        Ok(())
+    } else {
+        Err(type_error!(
+            "{} cannot be rescued because it is not a subclass of Exception",
+            class
+        ))
+    }
+}
+
+/// Check the values of `next` in a block conform to the type of the block
+/// and the return type of the block expected by the method (if known).
+/// A bare `next` has the type `Void`
+pub fn check_next_arg_tys(
+    class_dict: &ClassDict,
+    next_arg_tys: &[TermTy],
+    block_ty: &TermTy,
+    expected_ty: Option<&TermTy>,
+) -> Result<(), Error> {
+    // The values are just discarded
+    if block_ty.is_void_type() || expected_ty.map_or(false, |t| t.is_void_type()) {
+        return Ok(());
+    }
+    for ty in next_arg_tys {
+        if ty.is_error_type() {
+            continue;
+        }
+        let expected = expected_ty.unwrap_or(block_ty);
+        if !ty.conforms_to(block_ty, class_dict) || !ty.conforms_to(expected, class_dict) {
+            return Err(type_error!(
+                "the value of `next' should be {} but got {}",
+                expected.fullname,
+                ty.fullname
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Check the type of the argument of `return`
 pub fn check_return_arg_type(class_dict: &ClassDict, return_arg_ty: &TermTy, method_sig: &MethodSignature) -> Result<(), Error> {
     if return_arg_ty.conforms_to(&method_sig.ret_ty, class_dict) {
@@ -77,7 +207,7 @@ pub fn check_return_arg_type(class_dict: &ClassDict, return_arg_ty: &TermTy, met
 }
 
 pub fn check_reassign_var(orig_ty: &TermTy, new_ty: &TermTy, name: &str) -> Result<(), Error> {
-    if orig_ty.equals_to(new_ty) {
+    if orig_ty.equals_to(new_ty) || orig_ty.is_error_type() || new_ty.is_error_type() {
         Ok(())
     } else {
         Err(type_error!(
@@ -107,11 +237,25 @@ fn check_method_arity(
     receiver_hir: &hir::HirExpression,
     arg_hirs: &[hir::HirExpression],
 ) -> Result<(), Error> {
-    if sig.params.len() != arg_tys.len() {
+    let min = sig.required_arity();
+    // A rest param takes any number of args
+    let max = if sig.rest_param_idx().is_some() {
+        usize::MAX
+    } else {
+        sig.params.len()
+    };
+    if arg_tys.len() < min || max < arg_tys.len() {
+        let n_params = if min == max {
+            format!("{}", max)
+        } else if max == usize::MAX {
+            format!("{}..", min)
+        } else {
+            format!("{}..{}", min, max)
+        };
         return Err(type_error!(
             "{} takes {} args but got {} (receiver: {:?}, args: {:?})",
             sig.fullname,
-            sig.params.len(),
+            n_params,
             arg_tys.len(),
             receiver_hir,
             arg_hirs
@@ -127,13 +271,19 @@ fn check_arg_types(
     receiver_hir: &hir::HirExpression,
     arg_hirs: &[hir::HirExpression],
 ) -> Result<(), Error> {
-    for (param, arg_ty) in sig.params.iter().zip(arg_tys.iter()) {
-        if !arg_ty.conforms_to(&param.ty, class_dict) {
+    for (i, arg_ty) in arg_tys.iter().enumerate() {
+        let param = match sig.params.get(i) {
+            Some(param) => param,
+            // The rest of the args are for the rest param
+            None => sig.params.last().unwrap(),
+        };
+        let param_ty = param.item_ty();
+        if !arg_ty.conforms_to(param_ty, class_dict) {
             return Err(type_error!(
                 "the argument `{}' of `{}' should be {} but got {} (receiver: {:?}, args: {:?})",
                 param.name,
                 sig.fullname,
-                param.ty.fullname,
+                param_ty.fullname,
                 arg_ty.fullname,
                 receiver_hir,
                 arg_hirs
@@ -142,3 +292,34 @@ fn check_arg_types(
     }
     Ok(())
 }
+
+/// Check the arg given with `*` is passed to the rest param
+/// (the params with a default value before it may be omitted)
+pub fn check_splat_arg(
+    class_dict: &ClassDict,
+    sig: &MethodSignature,
+    n_args_before: usize,
+    splat_ty: &TermTy,
+) -> Result<(), Error> {
+    match sig.rest_param_idx() {
+        Some(idx) if sig.required_arity() <= n_args_before && n_args_before <= idx => {
+            let param = &sig.params[idx];
+            if splat_ty.conforms_to(&param.ty, class_dict) {
+                Ok(())
+            } else {
+                Err(type_error!(
+                    "the argument `{}' of `{}' should be {} but got {}",
+                    param.name,
+                    sig.fullname,
+                    param.ty.fullname,
+                    splat_ty.fullname
+                ))
+            }
+        }
+        _ => Err(type_error!(
+            "{} does not have a rest param at #{} for the `*' arg",
+            sig.fullname,
+            n_args_before + 1
+        )),
+    }
+}
@@ -0,0 +1,81 @@
+use shiika::diagnostics;
+use shiika::error;
+use shiika::location::LocationSpan;
+use shiika::parser::lexer::Cursor;
+use std::rc::Rc;
+
+fn cursor(line: usize, col: usize) -> Cursor {
+    Cursor { line, col, pos: 0 }
+}
+
+#[test]
+fn test_render_with_source() {
+    let src = "a = 1\nfoo.bar(\"a\")\n";
+    let span = LocationSpan::new(&Rc::new("x.sk".to_string()), cursor(1, 8), cursor(1, 11));
+    let err = error::type_error("expected Int but got String")
+        .located_at(&Some(span))
+        .with_help("pass an Int");
+    assert_eq!(
+        diagnostics::render_with_source(&err, "x.sk", Some(src)),
+        "error[E0003]: type error: expected Int but got String
+ --> x.sk:2:9
+  |
+2 | foo.bar(\"a\")
+  |         ^^^
+  = help: pass an Int
+"
+    );
+}
+
+#[test]
+fn test_render_without_span() {
+    let err = error::name_error("variable `x' was not found");
+    assert_eq!(
+        diagnostics::render_with_source(&err, "", None),
+        "error[E0004]: name error: variable `x' was not found\n"
+    );
+}
+
+#[test]
+fn test_parse_error_location() {
+    let err = shiika::parser::Parser::parse_file("1 +\n)", "y.sk").unwrap_err();
+    let span = err.span.unwrap();
+    assert_eq!(*span.filepath, "y.sk");
+    assert_eq!((span.begin.line, span.begin.col), (1, 0));
+}
+
+#[test]
+fn test_parser_recovery() {
+    let src = "class A\n  def foo(\n  end\n  def bar\n    1 +\n  end\nend\nx = )\n";
+    let err = shiika::parser::Parser::parse(src).unwrap_err();
+    let lines = match &err.details {
+        error::ErrorDetails::Multiple(errs) => errs
+            .iter()
+            .map(|e| e.span.as_ref().unwrap().begin.line)
+            .collect::<Vec<_>>(),
+        _ => panic!("unexpected: {:?}", err),
+    };
+    assert_eq!(lines, vec![2, 5, 7]);
+    assert!(diagnostics::render_with_source(&err, "", Some(src))
+        .ends_with("error: aborting due to 3 previous errors\n"));
+}
+
+#[test]
+fn test_render_errors_in_different_files() {
+    let src = "a = 1\nb = 2\n";
+    let span_x = LocationSpan::new(&Rc::new("x.sk".to_string()), cursor(0, 0), cursor(0, 1));
+    let span_y = LocationSpan::new(
+        &Rc::new("no_such_file.sk".to_string()),
+        cursor(1, 0),
+        cursor(1, 1),
+    );
+    let err = error::errors(vec![
+        error::name_error("error in x").located_at(&Some(span_x)),
+        error::name_error("error in y").located_at(&Some(span_y)),
+    ]);
+    let out = diagnostics::render_with_source(&err, "x.sk", Some(src));
+    // The line of x.sk is shown only for the error in x.sk
+    assert!(out.contains("1 | a = 1\n"));
+    assert!(!out.contains("b = 2"));
+    assert!(out.contains("--> no_such_file.sk:2:1\n"));
+}
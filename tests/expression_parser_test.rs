@@ -1,5 +1,6 @@
 use shiika::ast;
 use shiika::names;
+use shiika::parser::token::Token;
 use shiika::parser::Parser;
 
 fn parse_expr(src: &str) -> Result<ast::AstExpression, shiika::error::Error> {
@@ -33,6 +34,65 @@ fn test_if_expr_with_sep() {
     )
 }
 
+#[test]
+fn test_expr_span() {
+    let expr = parse_expr("1 +\n  foo").unwrap();
+    let span = expr.span.clone().unwrap();
+    assert_eq!((span.begin.line, span.begin.col), (0, 0));
+    assert_eq!((span.end.line, span.end.col), (1, 5));
+    match expr.body {
+        ast::AstExpressionBody::MethodCall { arg_exprs, .. } => {
+            let arg_span = arg_exprs[0].span.clone().unwrap();
+            assert_eq!((arg_span.begin.line, arg_span.begin.col), (1, 2));
+        }
+        _ => panic!("unexpected: {:?}", expr),
+    }
+}
+
+#[test]
+fn test_match_expr() {
+    let result = parse_expr("match x\nwhen Leaf(v) then v\nwhen Node then 0\nelse 1\nend");
+    assert_eq!(
+        result.unwrap(),
+        ast::match_expr(
+            ast::bare_name("x"),
+            vec![
+                ast::AstMatchClause {
+                    class_name: names::const_name(vec!["Leaf".to_string()]),
+                    bindings: vec!["v".to_string()],
+                    body_exprs: vec![ast::bare_name("v")],
+                },
+                ast::AstMatchClause {
+                    class_name: names::const_name(vec!["Node".to_string()]),
+                    bindings: vec![],
+                    body_exprs: vec![ast::decimal_literal(0)],
+                },
+            ],
+            Some(vec![ast::decimal_literal(1)])
+        )
+    )
+}
+
+#[test]
+fn test_begin_expr() {
+    let result = parse_expr("begin\n  foo\nrescue A, B => e\n  e\nensure\n  1\nend");
+    assert_eq!(
+        result.unwrap(),
+        ast::begin_expr(
+            vec![ast::bare_name("foo")],
+            vec![ast::AstRescueClause {
+                class_names: vec![
+                    names::const_name(vec!["A".to_string()]),
+                    names::const_name(vec!["B".to_string()]),
+                ],
+                var_name: Some("e".to_string()),
+                body_exprs: vec![ast::bare_name("e")],
+            }],
+            Some(vec![ast::decimal_literal(1)])
+        )
+    )
+}
+
 #[test]
 fn test_const_assign() {
     let result = parse_expr("X = 1");
@@ -231,6 +291,22 @@ fn test_call_with_dot() {
     )
 }
 
+#[test]
+fn test_call_keyword_named_method() {
+    let result = parse_expr("x.class");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("x")),
+            "class",
+            vec![],
+            vec![],
+            true,
+            true
+        )
+    )
+}
+
 //
 // Method call (1 arg)
 //
@@ -302,3 +378,268 @@ fn test_call_with_space_2() {
         )
     )
 }
+
+#[test]
+fn test_call_with_keyword_args() {
+    let result = parse_expr("foo(1, b: 2)");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            None,
+            "foo",
+            vec![
+                ast::decimal_literal(1),
+                ast::keyword_arg("b".to_string(), ast::decimal_literal(2)),
+            ],
+            vec![],
+            true,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_positional_arg_after_keyword_arg() {
+    let result = parse_expr("foo(a: 1, 2)");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_call_with_splat_arg() {
+    let result = parse_expr("foo(1, *a)");
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            None,
+            "foo",
+            vec![ast::decimal_literal(1), ast::splat_arg(ast::bare_name("a")),],
+            vec![],
+            true,
+            false
+        )
+    )
+}
+
+#[test]
+fn test_arg_after_splat_arg() {
+    let result = parse_expr("foo(*a, 1)");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_block_with_untyped_param() {
+    let result = parse_expr("a.each{|x| x}");
+    let param = ast::Param {
+        name: "x".to_string(),
+        typ: None,
+        is_iparam: false,
+        is_rest: false,
+        default_expr: None,
+        destructure: None,
+    };
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("a")),
+            "each",
+            vec![ast::lambda_expr(
+                vec![param],
+                vec![ast::bare_name("x")],
+                false
+            )],
+            vec![],
+            true,
+            true
+        )
+    )
+}
+
+#[test]
+fn test_block_with_destructuring_param() {
+    let result = parse_expr("a.each{|(k, v)| k}");
+    let param = ast::Param {
+        name: "(k, v)".to_string(),
+        typ: None,
+        is_iparam: false,
+        is_rest: false,
+        default_expr: None,
+        destructure: Some(vec!["k".to_string(), "v".to_string()]),
+    };
+    let destructuring = ast::multiple_assignment(
+        vec!["k".to_string(), "v".to_string()],
+        vec![ast::bare_name("(k, v)")],
+    );
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(
+            Some(ast::bare_name("a")),
+            "each",
+            vec![ast::lambda_expr(
+                vec![param],
+                vec![destructuring, ast::bare_name("k")],
+                false
+            )],
+            vec![],
+            true,
+            true
+        )
+    )
+}
+
+#[test]
+fn test_range_expr() {
+    let result = parse_expr("1..x + 1");
+    assert_eq!(
+        result.unwrap(),
+        ast::range_expr(
+            ast::decimal_literal(1),
+            ast::method_call(
+                Some(ast::bare_name("x")),
+                "+",
+                vec![ast::decimal_literal(1)],
+                vec![],
+                false,
+                false
+            ),
+            false
+        )
+    )
+}
+
+#[test]
+fn test_exclusive_range_expr() {
+    let result = parse_expr("0...n");
+    assert_eq!(
+        result.unwrap(),
+        ast::range_expr(ast::decimal_literal(0), ast::bare_name("n"), true)
+    )
+}
+
+#[test]
+fn test_case_expr() {
+    let result = parse_expr("case x\nwhen 1, 2 then 3\nwhen 4\n  5\nelse 6\nend");
+    assert_eq!(
+        result.unwrap(),
+        ast::case_expr(
+            ast::bare_name("x"),
+            vec![
+                ast::AstCaseClause {
+                    values: vec![ast::decimal_literal(1), ast::decimal_literal(2)],
+                    body_exprs: vec![ast::decimal_literal(3)],
+                },
+                ast::AstCaseClause {
+                    values: vec![ast::decimal_literal(4)],
+                    body_exprs: vec![ast::decimal_literal(5)],
+                },
+            ],
+            Some(vec![ast::decimal_literal(6)])
+        )
+    )
+}
+
+#[test]
+fn test_next_expr() {
+    let result = parse_expr("next");
+    assert_eq!(result.unwrap(), ast::next_expr(None))
+}
+
+#[test]
+fn test_next_expr_with_value() {
+    let result = parse_expr("next 1");
+    assert_eq!(
+        result.unwrap(),
+        ast::next_expr(Some(ast::decimal_literal(1)))
+    )
+}
+
+#[test]
+fn test_until_expr() {
+    let result = parse_expr("until a\nend");
+    assert_eq!(
+        result.unwrap(),
+        ast::while_expr(ast::logical_not(ast::bare_name("a")), vec![])
+    )
+}
+
+#[test]
+fn test_loop_expr() {
+    let result = parse_expr("loop do\nend");
+    assert_eq!(
+        result.unwrap(),
+        ast::while_expr(ast::pseudo_variable(Token::KwTrue), vec![])
+    )
+}
+
+#[test]
+fn test_for_expr() {
+    let result = parse_expr("for x in a\n  x\nend");
+    assert_eq!(
+        result.unwrap(),
+        ast::for_expr(
+            "x".to_string(),
+            ast::bare_name("a"),
+            vec![ast::bare_name("x")]
+        )
+    )
+}
+
+#[test]
+fn test_multiple_assignment() {
+    let result = parse_expr("a, b = b, a");
+    assert_eq!(
+        result.unwrap(),
+        ast::multiple_assignment(
+            vec!["a".to_string(), "b".to_string()],
+            vec![ast::bare_name("b"), ast::bare_name("a")]
+        )
+    )
+}
+
+#[test]
+fn test_multiple_assignment_from_pair() {
+    let result = parse_expr("a, b = pair");
+    assert_eq!(
+        result.unwrap(),
+        ast::multiple_assignment(
+            vec!["a".to_string(), "b".to_string()],
+            vec![ast::bare_name("pair")]
+        )
+    )
+}
+
+#[test]
+fn test_multiple_assignment_count_mismatch() {
+    let result = parse_expr("a, b = 1, 2, 3");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_tuple_literal() {
+    let result = parse_expr("(a, b)");
+    assert_eq!(
+        result.unwrap(),
+        ast::tuple_literal(vec![ast::bare_name("a"), ast::bare_name("b")])
+    )
+}
+
+#[test]
+fn test_hash_literal() {
+    let result = parse_expr("{1 => a, 2 => b}");
+    assert_eq!(
+        result.unwrap(),
+        ast::hash_literal(vec![
+            (ast::decimal_literal(1), ast::bare_name("a")),
+            (ast::decimal_literal(2), ast::bare_name("b")),
+        ])
+    )
+}
+
+#[test]
+fn test_tuple_element() {
+    let result = parse_expr("t.0.1");
+    let t0 = ast::method_call(Some(ast::bare_name("t")), "0", vec![], vec![], true, false);
+    assert_eq!(
+        result.unwrap(),
+        ast::method_call(Some(t0), "1", vec![], vec![], true, false)
+    )
+}
@@ -0,0 +1,291 @@
+use shiika::error::{Error, ErrorDetails};
+
+/// Type-check `src` and return the error messages
+fn errors_of(src: &str) -> Vec<String> {
+    match shiika::runner::check_src(src) {
+        Ok(_) => panic!("no error found in:\n{}", src),
+        Err(Error {
+            details: ErrorDetails::Multiple(errs),
+            ..
+        }) => errs.into_iter().map(|e| e.msg).collect(),
+        Err(e) => vec![e.msg],
+    }
+}
+
+fn assert_error(src: &str, expected: &str) {
+    let msgs = errors_of(src);
+    assert!(
+        msgs.iter().any(|msg| msg.contains(expected)),
+        "expected `{}' but got {:?}",
+        expected,
+        msgs
+    );
+}
+
+#[test]
+fn test_match_on_class_needs_else() {
+    let src = "
+class Animal
+end
+class Dog : Animal
+end
+class Cat : Animal
+end
+class Zoo
+  def self.name_of(a: Animal) -> String
+    match a
+    when Dog then \"dog\"
+    when Cat then \"cat\"
+    end
+  end
+end
+";
+    assert_error(
+        src,
+        "match on Animal is not exhaustive (Animal is not covered)",
+    );
+}
+
+#[test]
+fn test_default_value_is_checked_at_definition() {
+    let src = "
+class A
+  def foo(a: Int = \"x\") -> Int
+    a
+  end
+end
+";
+    assert_error(src, "the default value of `a' should be Int but got String");
+}
+
+#[test]
+fn test_default_value_cannot_refer_succeeding_params() {
+    let src = "
+class A
+  def foo(a: Int = b, b: Int = 1) -> Int
+    a
+  end
+end
+";
+    assert_error(src, "variable `b' was not found");
+}
+
+#[test]
+fn test_override_needs_default_value() {
+    let src = "
+class A
+  def foo(a: Int = 1) -> Int
+    a
+  end
+end
+class B : A
+  def foo(a: Int) -> Int
+    a
+  end
+end
+";
+    assert_error(
+        src,
+        "param `a' of B#foo needs a default value because A#foo has one",
+    );
+}
+
+#[test]
+fn test_bare_next_in_block_with_value() {
+    let src = "
+[1, 2].map<Int> do |n: Int|
+  next if n == 1
+  n
+end
+";
+    assert_error(src, "the value of `next' should be Int but got Void");
+}
+
+#[test]
+fn test_next_value_conforms_to_expected_type() {
+    let src = "
+[1, 2].map<Int> do |n: Int|
+  next \"x\" if n == 1
+  n
+end
+";
+    assert_error(src, "the value of `next' should be Int but got String");
+}
+
+#[test]
+fn test_return_in_for_over_range() {
+    let src = "
+class A
+  def self.foo -> Int
+    for i in 1..3
+      return i
+    end
+    0
+  end
+end
+";
+    assert_error(src, "`return' cannot be used in `for' except for an Array");
+}
+
+#[test]
+fn test_positional_arg_after_keyword_arg() {
+    let src = "
+class A
+  def self.foo(a: Int, f: Fn1<Int, Int>) -> Int
+    f(a)
+  end
+end
+A.foo(a: 1, fn(x: Int){ x })
+";
+    assert_error(src, "positional args cannot follow keyword args");
+}
+
+#[test]
+fn test_never_type_arg_is_not_covariant() {
+    let src = "
+class A
+  def self.foo(a: Array<Int>)
+  end
+end
+A.foo(Array<Never>.new)
+";
+    assert_error(src, "should be Array<Int> but got Array<Never>");
+}
+
+#[test]
+fn test_type_arg_must_conform_to_self_referencing_bound() {
+    let src = "
+class Util
+  def self.max<T : Comparable<T>>(a: T, b: T) -> T
+    if a < b then b else a end
+  end
+end
+Util.max<Object>(1, 2)
+";
+    assert_error(
+        src,
+        "type argument Object does not conform to Comparable<Object>",
+    );
+}
+
+#[test]
+fn test_range_of_non_discrete_values() {
+    let src = "
+(\"a\"..\"c\").each do |s: String|
+  puts s
+end
+";
+    assert_error(
+        src,
+        "type argument String does not conform to Discrete<String>",
+    );
+}
+
+#[test]
+fn test_signature_must_match_generic_interface() {
+    let src = "
+class Num : Comparable<Int>
+  def <(other: Num) -> Bool
+    true
+  end
+end
+";
+    assert_error(src, "the signature of Num#< does not match Comparable#<");
+}
+
+#[test]
+fn test_tuple_size_is_limited() {
+    let src = "
+t = (1, 2, 3, 4, 5, 6, 7, 8, 9, 10)
+";
+    assert_error(src, "tuple with more than 9 elements is not supported");
+    let src = "
+class A
+  def self.foo(t: (Int, Int, Int, Int, Int, Int, Int, Int, Int, Int))
+  end
+end
+";
+    assert_error(src, "tuple with more than 9 elements is not supported");
+}
+
+#[test]
+fn test_ambiguous_type_arg() {
+    let src = "
+class A
+  def self.pick<T>(a: T, b: T) -> T
+    a
+  end
+end
+A.pick(1, \"a\")
+";
+    assert_error(
+        src,
+        "the type argument T of Meta:A#pick is ambiguous (Int or String)",
+    );
+}
+
+#[test]
+fn test_type_arg_cannot_be_inferred() {
+    let src = "
+class A
+  def self.make<T>(n: Int) -> Array<T>
+    Array<T>.new
+  end
+end
+A.make(1)
+";
+    assert_error(src, "cannot infer the type argument T of Meta:A#make");
+}
+
+#[test]
+fn test_splat_arg_needs_required_args() {
+    let src = "
+class A
+  def self.f(a: Int, b: Int, *r: Int)
+  end
+end
+A.f(1, *[2])
+";
+    assert_error(
+        src,
+        "Meta:A#f does not have a rest param at #2 for the `*' arg",
+    );
+}
+
+#[test]
+fn test_keep_going_after_type_error() {
+    let src = "
+class A
+  def foo -> Int
+    \"a\"
+  end
+end
+x = 1 + true
+x.bar
+A.new.baz
+";
+    let msgs = errors_of(src);
+    // `x.bar` is not reported because `x` already has an error
+    assert_eq!(msgs.len(), 3, "{:?}", msgs);
+    assert!(msgs[0].contains("A#foo should return Int"), "{:?}", msgs);
+    assert!(msgs[1].contains("should be Int but got Bool"), "{:?}", msgs);
+    assert!(msgs[2].contains("baz"), "{:?}", msgs);
+}
+
+#[test]
+fn test_keep_going_after_indexing_error() {
+    let src = "
+class A : Comparable<Int, Int>
+end
+class B : NoSuchInterface<Int>
+end
+";
+    let msgs = errors_of(src);
+    assert_eq!(msgs.len(), 2, "{:?}", msgs);
+    assert!(
+        msgs[0].contains("wrong number of type arguments"),
+        "{:?}",
+        msgs
+    );
+    assert!(msgs[1].contains("unknown interface"), "{:?}", msgs);
+}